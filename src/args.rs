@@ -16,7 +16,21 @@ enum Command {
         name = "sync",
         about = "Syncs CDD projects using language-specific adaptors"
     )]
-    Sync,
+    Sync {
+        #[structopt(
+            long = "from",
+            help = "Service whose new and changed models and routes are written into the spec first"
+        )]
+        from: Option<String>,
+    },
+    #[structopt(
+        name = "bootstrap",
+        about = "Writes the OpenAPI spec from the models and routes of an existing service"
+    )]
+    Bootstrap {
+        #[structopt(help = "Service in config.yml to read", name = "service")]
+        service: String,
+    },
 }
 
 /// Compiler driven development cli tool
@@ -32,6 +46,7 @@ struct Opt {
     verbose: u8,
 
     /// Optionally specifies a config file location.
+    #[allow(dead_code)]
     #[structopt(help = "Config file location", parse(from_os_str), name = "config")]
     config: Option<PathBuf>,
 }
@@ -42,6 +57,7 @@ pub fn run() -> CliResult<()> {
 
     match opt.cmd {
         Command::Init { name } => crate::commands::init(&name),
-        Command::Sync => crate::commands::sync(),
+        Command::Sync { from } => crate::commands::sync(from.as_deref()),
+        Command::Bootstrap { service } => crate::commands::bootstrap(&service),
     }
 }
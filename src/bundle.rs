@@ -0,0 +1,330 @@
+use crate::*;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Load a spec, following `$ref`s into other files on disk, into a single document.
+/// Schemas from other files are added to `components.schemas` under the last segment of
+/// their reference (or the file name); anything else is inlined where it is referenced.
+/// Swagger 2.0 documents are bundled the same way, with `definitions` in place of `components.schemas`.
+pub fn bundle(path: &Path) -> CliResult<Value> {
+    let root = std::fs::canonicalize(path)
+        .map_err(|e| failure::format_err!("Could not read {}: {}", path.display(), e))?;
+    let mut bundler = Bundler {
+        root: root.clone(),
+        documents: HashMap::new(),
+        hoisted: HashMap::new(),
+        schemas: Map::new(),
+        schemas_pointer: "/components/schemas",
+        inlining: vec![],
+    };
+    let mut spec = bundler.document(&root)?.clone();
+    if convert::is_swagger(&spec) {
+        bundler.schemas_pointer = "/definitions";
+    }
+
+    // components that are just a reference to another file keep their own name
+    let root_schemas: Map<String, Value> = spec
+        .pointer(bundler.schemas_pointer)
+        .and_then(Value::as_object)
+        .cloned()
+        .unwrap_or_default();
+    for (name, schema) in root_schemas.iter() {
+        if let Some(Value::String(reference)) = schema.get("$ref") {
+            if let Some(target) = bundler.target(&root, reference)? {
+                bundler.hoisted.insert(target, name.clone());
+            }
+        }
+    }
+    bundler.schemas = root_schemas;
+
+    bundler.walk(&mut spec, &root, Context::Document)?;
+
+    if !bundler.schemas.is_empty() {
+        let mut parent = spec
+            .as_object_mut()
+            .ok_or_else(|| failure::format_err!("{} is not an OpenAPI document", path.display()))?;
+        for key in bundler.schemas_pointer.split('/').skip(1) {
+            let child = parent
+                .entry(key)
+                .or_insert_with(|| Value::Object(Map::new()));
+            parent = child.as_object_mut().ok_or_else(|| {
+                failure::format_err!("{} in {} is not an object", key, path.display())
+            })?;
+        }
+        for (name, schema) in bundler.schemas {
+            parent.entry(name).or_insert(schema);
+        }
+    }
+
+    Ok(spec)
+}
+
+/// where in a document a value sits, which decides how a `$ref` to another file is bundled
+#[derive(Clone, Copy, PartialEq)]
+enum Context {
+    Document,
+    Components,
+    /// the `components.schemas` map itself
+    SchemaEntries,
+    /// a named schema in `components.schemas`
+    SchemaEntry,
+    Schema,
+    Other,
+}
+
+impl Context {
+    fn child(self, key: &str) -> Context {
+        match (self, key) {
+            (Context::Document, "components") => Context::Components,
+            (Context::Document, "definitions") => Context::SchemaEntries,
+            (Context::Components, "schemas") => Context::SchemaEntries,
+            (Context::SchemaEntries, _) => Context::SchemaEntry,
+            (Context::SchemaEntry, _) | (Context::Schema, _) | (_, "schema") => Context::Schema,
+            _ => Context::Other,
+        }
+    }
+}
+
+struct Bundler {
+    root: PathBuf,
+    documents: HashMap<PathBuf, Value>,
+    /// `file#pointer` -> name in `components.schemas`
+    hoisted: HashMap<String, String>,
+    /// `components.schemas`, including those pulled in from other files
+    schemas: Map<String, Value>,
+    /// where schemas live, `definitions` for Swagger 2.0
+    schemas_pointer: &'static str,
+    /// references being inlined, to catch cycles
+    inlining: Vec<String>,
+}
+
+impl Bundler {
+    fn document(&mut self, file: &Path) -> CliResult<&Value> {
+        if !self.documents.contains_key(file) {
+            let contents = std::fs::read_to_string(file)
+                .map_err(|e| failure::format_err!("Could not read {}: {}", file.display(), e))?;
+            let document: Value = match file.extension().and_then(|extension| extension.to_str()) {
+                Some("json") => serde_json::from_str(&contents).map_err(|e| {
+                    failure::format_err!("Could not parse {}: {}", file.display(), e)
+                })?,
+                _ => serde_yaml::from_str(&contents).map_err(|e| {
+                    failure::format_err!("Could not parse {}: {}", file.display(), e)
+                })?,
+            };
+            self.documents.insert(file.to_path_buf(), document);
+        }
+        Ok(&self.documents[file])
+    }
+
+    /// `file#pointer` for a reference made from `file`, or `None` for one local to the root spec
+    fn target(&self, file: &Path, reference: &str) -> CliResult<Option<String>> {
+        let (path, pointer) = match reference.find('#') {
+            Some(index) => (&reference[..index], &reference[index + 1..]),
+            None => (reference, ""),
+        };
+        if path.contains("://") && !path.starts_with("file://") {
+            return Err(failure::format_err!(
+                "Unsupported reference in {}: {} is not a file on disk",
+                file.display(),
+                reference
+            ));
+        }
+        if path.is_empty() && file == self.root {
+            return Ok(None);
+        }
+
+        let target_file = if path.is_empty() {
+            file.to_path_buf()
+        } else {
+            let relative = file
+                .parent()
+                .unwrap_or_else(|| Path::new("."))
+                .join(path.trim_start_matches("file://"));
+            std::fs::canonicalize(&relative).map_err(|_| {
+                failure::format_err!(
+                    "Unresolved reference in {}: {} does not exist",
+                    file.display(),
+                    relative.display()
+                )
+            })?
+        };
+        let pointer = if pointer.is_empty() || pointer.starts_with('/') {
+            pointer.to_string()
+        } else {
+            format!("/{}", pointer)
+        };
+        Ok(Some(format!("{}#{}", target_file.display(), pointer)))
+    }
+
+    fn resolve(&mut self, target: &str) -> CliResult<Value> {
+        let (file, pointer) = split_target(target);
+        self.document(&file)?
+            .pointer(pointer)
+            .cloned()
+            .ok_or_else(|| {
+                failure::format_err!(
+                    "Unresolved reference: {} has nothing at {}",
+                    file.display(),
+                    pointer
+                )
+            })
+    }
+
+    fn walk(&mut self, value: &mut Value, file: &Path, context: Context) -> CliResult<()> {
+        match value {
+            Value::Object(map) => {
+                if let Some(Value::String(reference)) = map.get("$ref") {
+                    if let Some(target) = self.target(file, &reference.clone())? {
+                        let (target_file, pointer) = split_target(&target);
+                        *value = if target_file == self.root {
+                            // another file pointing back into the root spec
+                            serde_json::json!({ "$ref": format!("#{}", pointer) })
+                        } else if context == Context::Schema {
+                            let name = self.hoist(&target)?;
+                            serde_json::json!({ "$ref": format!("#{}/{}", self.schemas_pointer, name) })
+                        } else {
+                            self.inline(&target, context)?
+                        };
+                    }
+                    return Ok(());
+                }
+                for (key, child) in map.iter_mut() {
+                    self.walk(child, file, context.child(key))?;
+                }
+            }
+            Value::Array(items) => {
+                for item in items.iter_mut() {
+                    self.walk(item, file, context)?;
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// add a referenced schema to `components.schemas`, returning its name there
+    fn hoist(&mut self, target: &str) -> CliResult<String> {
+        if let Some(name) = self.hoisted.get(target) {
+            return Ok(name.clone());
+        }
+
+        let (file, pointer) = split_target(target);
+        let name = match pointer.rsplit('/').next() {
+            Some(segment) if !segment.is_empty() => segment.to_string(),
+            _ => file
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .unwrap_or("")
+                .to_string(),
+        };
+        if self.schemas.contains_key(&name) {
+            return Err(failure::format_err!(
+                "Cannot bundle {}: components.schemas already has a {}",
+                target,
+                name
+            ));
+        }
+
+        // register before walking so self-referencing schemas terminate
+        self.hoisted.insert(target.to_string(), name.clone());
+        self.schemas.insert(name.clone(), Value::Null);
+        let mut schema = self.resolve(target)?;
+        self.walk(&mut schema, &file, Context::Schema)?;
+        self.schemas.insert(name.clone(), schema);
+        Ok(name)
+    }
+
+    /// replace a reference with what it points to
+    fn inline(&mut self, target: &str, context: Context) -> CliResult<Value> {
+        if self.inlining.iter().any(|inlining| inlining == target) {
+            return Err(failure::format_err!(
+                "Circular reference: {} -> {}",
+                self.inlining.join(" -> "),
+                target
+            ));
+        }
+
+        self.inlining.push(target.to_string());
+        let mut value = self.resolve(target)?;
+        let context = match context {
+            Context::SchemaEntry => Context::Schema,
+            context => context,
+        };
+        self.walk(&mut value, &split_target(target).0, context)?;
+        self.inlining.pop();
+        Ok(value)
+    }
+}
+
+/// `file#pointer` -> (file, pointer)
+fn split_target(target: &str) -> (PathBuf, &str) {
+    let index = target.rfind('#').unwrap_or(target.len());
+    (
+        PathBuf::from(&target[..index]),
+        target.get(index + 1..).unwrap_or(""),
+    )
+}
+
+#[test]
+fn test_bundle() {
+    let dir = std::env::temp_dir().join(format!("cdd-bundle-{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("schemas")).unwrap();
+    std::fs::write(
+        dir.join("openapi.yml"),
+        r#"
+openapi: 3.0.0
+info: { title: test, version: 0.0.1 }
+paths:
+  /pets: { $ref: './paths/pets.yaml' }
+components:
+  schemas:
+    Pet: { $ref: './schemas/pet.yaml#/Pet' }
+"#,
+    )
+    .unwrap();
+    std::fs::create_dir_all(dir.join("paths")).unwrap();
+    std::fs::write(
+        dir.join("paths/pets.yaml"),
+        r#"
+get:
+  responses:
+    '200':
+      description: ok
+      content:
+        application/json: { schema: { $ref: '../schemas/pet.yaml#/Pet' } }
+"#,
+    )
+    .unwrap();
+    std::fs::write(
+        dir.join("schemas/pet.yaml"),
+        r#"
+Pet:
+  type: object
+  properties:
+    owner: { $ref: '#/Owner' }
+Owner:
+  type: object
+  properties:
+    pets: { type: array, items: { $ref: '#/Pet' } }
+"#,
+    )
+    .unwrap();
+
+    let spec = bundle(&dir.join("openapi.yml")).unwrap();
+    let project = Project::parse_yml(serde_json::from_value(spec).unwrap()).unwrap();
+    let mut names = project
+        .models
+        .iter()
+        .map(|model| model.name.clone())
+        .collect::<Vec<_>>();
+    names.sort();
+    assert_eq!(names, vec!["Owner", "Pet"]);
+    assert_eq!(project.requests[0].response_type, "Pet");
+
+    std::fs::write(dir.join("paths/pets.yaml"), "$ref: './pets.yaml'").unwrap();
+    let err = bundle(&dir.join("openapi.yml")).unwrap_err();
+    assert!(format!("{}", err).starts_with("Circular reference"));
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
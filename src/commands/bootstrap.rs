@@ -0,0 +1,56 @@
+use crate::*;
+use log::*;
+use std::path::PathBuf;
+
+/// Write the spec from the models and requests of an existing service,
+/// for projects adopting cdd from code rather than from a spec.
+pub fn bootstrap(service_name: &str) -> CliResult<()> {
+    let project_path = PathBuf::from(".");
+    let config = config::Config::read(project_path.join("config.yml"))?;
+    let spec_path = config
+        .spec_paths(&project_path)
+        .into_iter()
+        .next()
+        .ok_or_else(|| failure::format_err!("config.yml names no spec file"))?;
+    if spec_path.exists() {
+        return Err(failure::format_err!(
+            "{} already exists.",
+            spec_path.display()
+        ));
+    }
+
+    let service = config.services.get(service_name).ok_or_else(|| {
+        failure::format_err!("There is no service {} in config.yml", service_name)
+    })?;
+    let extracted = service.extract_project(&service.existing_request_files())?;
+    let project = Project {
+        info: project::Info::default(),
+        models: extracted.models,
+        requests: extracted
+            .requests
+            .into_iter()
+            .map(|(_file, request)| request)
+            .collect(),
+        security_schemes: vec![],
+    };
+
+    let mut spec = serde_json::to_value(project.to_openapi(&config.name, &config.version)?)?;
+    project::declare_keys(&mut spec, &project.models);
+    let contents = match spec_path
+        .extension()
+        .and_then(|extension| extension.to_str())
+    {
+        Some("json") => serde_json::to_string_pretty(&spec)?,
+        _ => serde_yaml::to_string(&spec)?,
+    };
+    util::write_file(spec_path.clone(), &contents)?;
+    info!(
+        "Wrote {} models, {} routes from {} to {}",
+        project.models.len(),
+        project.requests.len(),
+        service_name,
+        spec_path.display()
+    );
+
+    Ok(())
+}
@@ -4,8 +4,8 @@ use log::*;
 use std::path::PathBuf;
 
 pub fn init(name: &str) -> CliResult<()> {
-    let _ = init_config_file(name)?;
-    let _ = copy_openapi_spec()?;
+    init_config_file(name)?;
+    copy_openapi_spec()?;
 
     Ok(())
 }
@@ -1,3 +1,5 @@
+mod bootstrap;
+pub use bootstrap::*;
 mod init;
 pub use init::*;
 mod sync;
@@ -1,13 +1,16 @@
 use crate::*;
 use std::path::PathBuf;
 
-pub fn sync() -> CliResult<()> {
+pub fn sync(from: Option<&str>) -> CliResult<()> {
     let project_path = PathBuf::from(".");
-    let project_graph = project_graph::ProjectGraph::read(&project_path)?;
+    let mut project_graph = project_graph::ProjectGraph::read(&project_path)?;
 
     // ensure all projects are in place
     project_graph.copy_templates()?;
-    project_graph.simple_sync()?;
+    match from {
+        Some(service) => project_graph.sync_from(service)?,
+        None => project_graph.simple_sync()?,
+    }
 
     Ok(())
 }
@@ -1,10 +1,13 @@
 use crate::error::*;
+use crate::project::Key;
 use crate::service::*;
 use crate::util;
+use log::*;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
+#[allow(dead_code)]
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
 pub struct Component {
     tests: bool,
@@ -15,16 +18,38 @@ pub struct Component {
 
 #[derive(Serialize, Deserialize)]
 pub(crate) struct Config {
-    name: String,
-    version: String,
+    pub(crate) name: String,
+    pub(crate) version: String,
     description: String,
     author: String,
-    openapi: String,
-    auth: String,
+    openapi: SpecFiles,
+    /// key for models whose schema declares none with `x-cdd-key`/`x-keys`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) default_key: Option<Key>,
     pub(crate) services: HashMap<String, CDDService>,
 }
 
+/// `openapi` in config.yml: a single spec file, or a list of them merged into one project
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub(crate) enum SpecFiles {
+    Single(String),
+    Multiple(Vec<String>),
+}
+
 impl Config {
+    /// spec files named in the config, relative to the project at `path`
+    pub fn spec_paths(&self, path: &Path) -> Vec<PathBuf> {
+        let files = match &self.openapi {
+            SpecFiles::Single(file) => vec![file],
+            SpecFiles::Multiple(files) => files.iter().collect(),
+        };
+        files
+            .into_iter()
+            .map(|file| spec_path(path.join(file)))
+            .collect()
+    }
+
     /// Read a configuration file from an optional location, or try several default locations.
     pub fn read(file: PathBuf) -> CliResult<Self> {
         let file_contents: String = util::read_file(file).or(Err(failure::format_err!(
@@ -40,7 +65,7 @@ impl Config {
         let yaml = serde_yaml::to_string(&self)?;
         let mut output = File::create(path)?;
 
-        write!(output, "{}\n", yaml)?;
+        writeln!(output, "{}", yaml)?;
 
         Ok(())
     }
@@ -58,6 +83,7 @@ impl Config {
                 project_path: "./rust".to_string(),
                 component_file: "src/models.rs".to_string(),
                 requests_file: "src/routes.rs".to_string(),
+                tag_requests_file: None,
             },
         );
 
@@ -70,6 +96,7 @@ impl Config {
                 project_path: "./typescript".to_string(),
                 component_file: "API/Models.ts".to_string(),
                 requests_file: "API/Requests.ts".to_string(),
+                tag_requests_file: None,
             },
         );
 
@@ -82,6 +109,7 @@ impl Config {
                 project_path: "./kotlin".to_string(),
                 component_file: "API/Models.kt".to_string(),
                 requests_file: "API/Requests.kt".to_string(),
+                tag_requests_file: None,
             },
         );
 
@@ -95,6 +123,7 @@ impl Config {
                 project_path: "./iOS".to_string(),
                 component_file: "cddTemplate/Source/API/APIModels.swift".to_string(),
                 requests_file: "cddTemplate/Source/API/APIRequests.swift".to_string(),
+                tag_requests_file: None,
             },
         );
         Config {
@@ -102,16 +131,66 @@ impl Config {
             version: "0.0.1".to_string(),
             description: "description".to_string(),
             author: "me@me.com".to_string(),
-            openapi: "openapi.yaml".to_string(),
-            auth: "rfc6749".to_string(),
-<<<<<<< HEAD
-            rpc_services: vec![RPCService {
-                server: String::from("localhost:4444"),
-                exec: format!("{}/cdd-rust-rpc --port 4444", bin_path)
-            }],
-=======
->>>>>>> b18d64fb58f93b63b796c172732e2e3ef08d6848
+            openapi: SpecFiles::Single("openapi.yml".to_string()),
+            default_key: None,
             services,
         }
     }
 }
+
+/// older versions of `init` named `openapi.yaml` in config.yml but wrote `openapi.yml`,
+/// so a missing spec file is looked for under the other yaml extension too
+fn spec_path(configured: PathBuf) -> PathBuf {
+    let other = match configured
+        .extension()
+        .and_then(|extension| extension.to_str())
+    {
+        Some("yaml") => configured.with_extension("yml"),
+        Some("yml") => configured.with_extension("yaml"),
+        _ => return configured,
+    };
+    if !configured.exists() && other.exists() {
+        warn!(
+            "Could not find {}, using {}: update openapi in config.yml",
+            configured.display(),
+            other.display()
+        );
+        return other;
+    }
+    configured
+}
+
+#[test]
+fn test_spec_files() {
+    let config: Config = serde_yaml::from_str(
+        r#"
+name: test
+version: 0.0.1
+description: description
+author: me@me.com
+openapi: [pets.yaml, stores.json]
+services: {}
+"#,
+    )
+    .unwrap();
+    assert_eq!(
+        config.spec_paths(Path::new("api")),
+        vec![
+            PathBuf::from("api/pets.yaml"),
+            PathBuf::from("api/stores.json")
+        ]
+    );
+    assert_eq!(
+        Config::new("test").spec_paths(Path::new(".")),
+        vec![PathBuf::from("./openapi.yml")]
+    );
+
+    let dir = std::env::temp_dir().join(format!("cdd-spec-files-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("pets.yml"), "").unwrap();
+    assert_eq!(
+        config.spec_paths(&dir),
+        vec![dir.join("pets.yml"), dir.join("stores.json")]
+    );
+    std::fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,6 @@
+mod openapi31;
+mod security;
+mod swagger;
+pub use openapi31::*;
+pub use security::*;
+pub use swagger::*;
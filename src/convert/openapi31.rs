@@ -0,0 +1,302 @@
+use crate::*;
+use serde_json::{json, Map, Value};
+
+pub fn is_openapi_31(spec: &Value) -> bool {
+    spec.get("openapi")
+        .and_then(Value::as_str)
+        .is_some_and(|version| version.starts_with("3.1"))
+}
+
+/// Rewrite an OpenAPI 3.1 document, whose schemas are JSON Schema 2020-12, into the 3.0 dialect.
+/// `type: [x, "null"]` and unions with `{type: "null"}` become `nullable`, `const` a single
+/// value enum, `examples` the first example and `$defs` are moved into `components.schemas`.
+pub fn openapi_31_to_30(spec: Value) -> CliResult<Value> {
+    let mut spec = match spec {
+        Value::Object(spec) => spec,
+        _ => return Err(failure::format_err!("OpenAPI document is not an object")),
+    };
+    spec.insert("openapi".to_string(), json!("3.0.3"));
+    spec.remove("jsonSchemaDialect");
+    spec.remove("webhooks");
+    spec.entry("paths").or_insert_with(|| json!({}));
+    if let Some(Value::Object(license)) = spec
+        .get_mut("info")
+        .and_then(|info| info.get_mut("license"))
+    {
+        license.remove("identifier");
+    }
+
+    let mut definitions = Map::new();
+    let mut spec = Value::Object(spec);
+    normalise(&mut spec, false, &mut definitions)?;
+
+    if !definitions.is_empty() {
+        let schemas = spec
+            .as_object_mut()
+            .and_then(|spec| {
+                spec.entry("components")
+                    .or_insert_with(|| json!({}))
+                    .as_object_mut()
+            })
+            .and_then(|components| {
+                components
+                    .entry("schemas")
+                    .or_insert_with(|| json!({}))
+                    .as_object_mut()
+            })
+            .ok_or_else(|| failure::format_err!("components.schemas is not an object"))?;
+        for (name, schema) in definitions {
+            if schemas.contains_key(&name) {
+                return Err(failure::format_err!(
+                    "Cannot move $defs/{} into components.schemas: it already has a {}",
+                    name,
+                    name
+                ));
+            }
+            schemas.insert(name, schema);
+        }
+    }
+    rewrite_references(&mut spec);
+    Ok(spec)
+}
+
+/// walk the document, treating any value under a `schema`/`schemas` key as JSON Schema
+fn normalise(
+    value: &mut Value,
+    in_schema: bool,
+    definitions: &mut Map<String, Value>,
+) -> CliResult<()> {
+    match value {
+        Value::Object(object) => {
+            if in_schema {
+                normalise_schema(object, definitions)?;
+            }
+            for (key, child) in object.iter_mut() {
+                let named_schemas = match key.as_str() {
+                    "properties" | "patternProperties" => true,
+                    "schemas" => !in_schema,
+                    _ => false,
+                };
+                if named_schemas {
+                    if let Value::Object(named) = child {
+                        for schema in named.values_mut() {
+                            normalise(schema, true, definitions)?;
+                        }
+                    }
+                    continue;
+                }
+                match key.as_str() {
+                    "example" | "examples" | "default" | "enum" => {}
+                    "schema" => normalise(child, true, definitions)?,
+                    _ => normalise(child, in_schema, definitions)?,
+                }
+            }
+        }
+        Value::Array(items) => {
+            for item in items.iter_mut() {
+                normalise(item, in_schema, definitions)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn normalise_schema(
+    schema: &mut Map<String, Value>,
+    definitions: &mut Map<String, Value>,
+) -> CliResult<()> {
+    if let Some(Value::Object(defs)) = schema.remove("$defs") {
+        for (name, mut definition) in defs {
+            normalise(&mut definition, true, definitions)?;
+            if definitions.insert(name.clone(), definition).is_some() {
+                return Err(failure::format_err!(
+                    "$defs/{} is defined more than once",
+                    name
+                ));
+            }
+        }
+    }
+
+    if let Some(Value::Array(types)) = schema.get("type").cloned() {
+        let nullable = types.iter().any(|schema_type| schema_type == "null");
+        let types: Vec<Value> = types
+            .into_iter()
+            .filter(|schema_type| schema_type != "null")
+            .collect();
+        match types.len() {
+            1 => {
+                schema.insert("type".to_string(), types[0].clone());
+            }
+            _ => {
+                // several types have no 3.0 spelling, so accept anything
+                schema.remove("type");
+            }
+        }
+        if nullable {
+            schema.insert("nullable".to_string(), json!(true));
+        }
+    }
+
+    if let Some(value) = schema.remove("const") {
+        let schema_type = match &value {
+            Value::String(_) => Some("string"),
+            Value::Bool(_) => Some("boolean"),
+            Value::Number(number) if number.is_f64() => Some("number"),
+            Value::Number(_) => Some("integer"),
+            _ => None,
+        };
+        if let Some(schema_type) = schema_type {
+            schema.entry("type").or_insert_with(|| json!(schema_type));
+        }
+        schema.insert("enum".to_string(), json!([value]));
+    }
+    if let Some(Value::Array(examples)) = schema.remove("examples") {
+        if let Some(example) = examples.into_iter().next() {
+            schema.entry("example").or_insert(example);
+        }
+    }
+    for (exclusive, bound) in [
+        ("exclusiveMinimum", "minimum"),
+        ("exclusiveMaximum", "maximum"),
+    ]
+    .iter()
+    {
+        if let Some(limit) = schema
+            .get(*exclusive)
+            .filter(|limit| limit.is_number())
+            .cloned()
+        {
+            schema.insert(bound.to_string(), limit);
+            schema.insert(exclusive.to_string(), json!(true));
+        }
+    }
+    if schema.get("contentEncoding").and_then(Value::as_str) == Some("base64") {
+        schema.remove("contentEncoding");
+        schema.insert("format".to_string(), json!("byte"));
+    } else if schema.contains_key("contentMediaType")
+        && schema.get("type") == Some(&json!("string"))
+    {
+        schema.insert("format".to_string(), json!("binary"));
+    }
+    schema.remove("contentMediaType");
+
+    for union in ["oneOf", "anyOf"].iter() {
+        let variants = match schema.get(*union) {
+            Some(Value::Array(variants)) if variants.iter().any(is_null_schema) => variants.clone(),
+            _ => continue,
+        };
+        let mut variants: Vec<Value> = variants
+            .into_iter()
+            .filter(|variant| !is_null_schema(variant))
+            .collect();
+        schema.insert("nullable".to_string(), json!(true));
+        if variants.len() != 1 {
+            schema.insert(union.to_string(), Value::Array(variants));
+            continue;
+        }
+
+        schema.remove(*union);
+        match variants.remove(0) {
+            // the 3.0 spelling of a nullable reference
+            variant @ Value::Object(_) if variant.get("$ref").is_some() => {
+                schema.insert("allOf".to_string(), json!([variant]));
+            }
+            Value::Object(variant) => {
+                for (key, value) in variant {
+                    schema.entry(key).or_insert(value);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if schema.get("type") == Some(&json!("null")) {
+        schema.remove("type");
+        schema.insert("nullable".to_string(), json!(true));
+    }
+    Ok(())
+}
+
+fn is_null_schema(schema: &Value) -> bool {
+    schema.get("type") == Some(&json!("null"))
+}
+
+/// point references into `$defs` at their new home in `components.schemas`
+fn rewrite_references(value: &mut Value) {
+    match value {
+        Value::Object(object) => {
+            if let Some(Value::String(reference)) = object.get_mut("$ref") {
+                if let Some(index) = reference.rfind("/$defs/") {
+                    *reference = format!(
+                        "#/components/schemas/{}",
+                        &reference[index + "/$defs/".len()..]
+                    );
+                }
+            }
+            object.values_mut().for_each(rewrite_references);
+        }
+        Value::Array(items) => items.iter_mut().for_each(rewrite_references),
+        _ => {}
+    }
+}
+
+#[test]
+fn test_openapi_31_to_30() {
+    let spec: Value = serde_yaml::from_str(
+        r#"
+openapi: 3.1.0
+info: { title: petstore, version: 1.0.0, license: { name: MIT, identifier: MIT } }
+components:
+  schemas:
+    Pet:
+      type: object
+      required: [name, kind, tag, owner]
+      properties:
+        name: { type: string, examples: [rex] }
+        kind: { const: dog }
+        tag: { type: [string, "null"] }
+        id: { type: [string, integer] }
+        nothing: { const: null }
+        owner: { oneOf: [{ $ref: '#/components/schemas/Pet/$defs/Owner' }, { type: "null" }] }
+      $defs:
+        Owner:
+          type: object
+          properties:
+            age: { type: integer, exclusiveMinimum: 0 }
+"#,
+    )
+    .unwrap();
+    assert!(is_openapi_31(&spec));
+
+    let spec = openapi_31_to_30(spec).unwrap();
+    let age = &spec["components"]["schemas"]["Owner"]["properties"]["age"];
+    assert_eq!(age["minimum"], json!(0));
+    assert_eq!(age["exclusiveMinimum"], json!(true));
+    assert_eq!(
+        spec["components"]["schemas"]["Pet"]["properties"]["name"]["example"],
+        json!("rex")
+    );
+
+    let project = Project::parse_yml(serde_json::from_value(spec).unwrap()).unwrap();
+    let pet = project
+        .models
+        .iter()
+        .find(|model| model.name == "Pet")
+        .unwrap();
+    let var = |name: &str| pet.vars.iter().find(|var| var.name == name).unwrap();
+    assert!(!var("name").optional);
+    assert_eq!(
+        var("kind").variable_type,
+        VariableType::EnumType(vec!["dog".to_string()])
+    );
+    assert_eq!(var("tag").variable_type, VariableType::StringType);
+    assert!(var("tag").nullable && !var("tag").optional);
+    assert_eq!(
+        var("owner").variable_type,
+        VariableType::ComplexType("Owner".to_string())
+    );
+    assert!(var("owner").nullable);
+    assert_eq!(var("id").variable_type, VariableType::AnyType);
+    assert_eq!(var("nothing").variable_type, VariableType::AnyType);
+}
@@ -0,0 +1,29 @@
+use serde_json::Value;
+
+const METHODS: [&str; 8] = [
+    "get", "put", "post", "delete", "options", "head", "patch", "trace",
+];
+
+/// Copy top-level `security` into each operation that doesn't set its own, and drop it.
+/// `openapiv3` reads a missing operation `security` as `[]`, which would otherwise be
+/// indistinguishable from an operation explicitly opting out of authentication.
+pub fn inherit_security(spec: &mut Value) {
+    let security = match spec
+        .as_object_mut()
+        .and_then(|spec| spec.remove("security"))
+    {
+        Some(security) => security,
+        None => return,
+    };
+    if let Some(Value::Object(paths)) = spec.get_mut("paths") {
+        for path_item in paths.values_mut() {
+            for method in METHODS.iter() {
+                if let Some(Value::Object(operation)) = path_item.get_mut(*method) {
+                    operation
+                        .entry("security")
+                        .or_insert_with(|| security.clone());
+                }
+            }
+        }
+    }
+}
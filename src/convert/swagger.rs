@@ -0,0 +1,582 @@
+use crate::*;
+use serde_json::{json, Map, Value};
+
+const METHODS: [&str; 8] = [
+    "get", "put", "post", "delete", "options", "head", "patch", "trace",
+];
+
+/// keywords a non-body Swagger 2.0 parameter or header keeps inline, which live under `schema` in 3.0
+const SCHEMA_KEYWORDS: [&str; 16] = [
+    "type",
+    "format",
+    "items",
+    "enum",
+    "default",
+    "minimum",
+    "maximum",
+    "exclusiveMinimum",
+    "exclusiveMaximum",
+    "minLength",
+    "maxLength",
+    "pattern",
+    "minItems",
+    "maxItems",
+    "uniqueItems",
+    "multipleOf",
+];
+
+pub fn is_swagger(spec: &Value) -> bool {
+    spec.get("swagger").and_then(Value::as_str) == Some("2.0")
+}
+
+/// Convert a Swagger 2.0 document into the OpenAPI 3.0 equivalent.
+pub fn swagger_to_openapi(swagger: Value) -> CliResult<Value> {
+    let mut swagger = match swagger {
+        Value::Object(swagger) => swagger,
+        _ => return Err(failure::format_err!("Swagger document is not an object")),
+    };
+    let consumes = media_types(swagger.get("consumes"));
+    let produces = media_types(swagger.get("produces"));
+    let global_parameters = take_object(&mut swagger, "parameters");
+
+    let mut spec = Map::new();
+    spec.insert("openapi".to_string(), json!("3.0.0"));
+    if let Some(info) = swagger.remove("info") {
+        spec.insert("info".to_string(), info);
+    }
+    let base_path = swagger
+        .get("basePath")
+        .and_then(Value::as_str)
+        .unwrap_or("");
+    if let Some(host) = swagger.get("host").and_then(Value::as_str) {
+        let schemes = swagger
+            .get("schemes")
+            .and_then(Value::as_array)
+            .map(|schemes| {
+                schemes
+                    .iter()
+                    .filter_map(Value::as_str)
+                    .map(String::from)
+                    .collect()
+            })
+            .unwrap_or_else(|| vec!["https".to_string()]);
+        let servers: Vec<Value> = schemes
+            .iter()
+            .map(|scheme| json!({ "url": format!("{}://{}{}", scheme, host, base_path) }))
+            .collect();
+        spec.insert("servers".to_string(), Value::Array(servers));
+    } else if !base_path.is_empty() {
+        // no host means the one serving the document
+        spec.insert("servers".to_string(), json!([{ "url": base_path }]));
+    }
+
+    let mut components = Map::new();
+    let mut schemas = take_object(&mut swagger, "definitions");
+    for schema in schemas.values_mut() {
+        convert_schema(schema);
+    }
+    components.insert("schemas".to_string(), Value::Object(schemas));
+
+    let mut parameters = Map::new();
+    let mut request_bodies = Map::new();
+    for (name, parameter) in global_parameters.iter() {
+        match parameter.get("in").and_then(Value::as_str) {
+            Some("body") | Some("formData") => {
+                let body = request_body(std::slice::from_ref(parameter), &consumes);
+                request_bodies.insert(name.clone(), body);
+            }
+            _ => {
+                parameters.insert(name.clone(), convert_parameter(parameter.clone()));
+            }
+        }
+    }
+    components.insert("parameters".to_string(), Value::Object(parameters));
+    components.insert("requestBodies".to_string(), Value::Object(request_bodies));
+
+    let mut responses = take_object(&mut swagger, "responses");
+    for response in responses.values_mut() {
+        convert_response(response, &produces);
+    }
+    components.insert("responses".to_string(), Value::Object(responses));
+
+    let mut security_schemes = take_object(&mut swagger, "securityDefinitions");
+    for scheme in security_schemes.values_mut() {
+        convert_security_scheme(scheme);
+    }
+    components.insert(
+        "securitySchemes".to_string(),
+        Value::Object(security_schemes),
+    );
+    spec.insert("components".to_string(), Value::Object(components));
+
+    let mut paths = take_object(&mut swagger, "paths");
+    for path_item in paths.values_mut() {
+        if let Value::Object(path_item) = path_item {
+            // body parameters become each operation's request body, the rest stay shared
+            let (body_parameters, parameters): (Vec<Value>, Vec<Value>) =
+                match path_item.remove("parameters") {
+                    Some(Value::Array(parameters)) => parameters
+                        .into_iter()
+                        .partition(|parameter| is_body(parameter, &global_parameters)),
+                    _ => (vec![], vec![]),
+                };
+            if !parameters.is_empty() {
+                path_item.insert(
+                    "parameters".to_string(),
+                    Value::Array(parameters.into_iter().map(convert_parameter).collect()),
+                );
+            }
+            for method in METHODS.iter() {
+                if let Some(operation) = path_item.get_mut(*method) {
+                    inherit_parameters(operation, &body_parameters, &global_parameters);
+                    convert_operation(operation, &global_parameters, &consumes, &produces);
+                }
+            }
+        }
+    }
+    spec.insert("paths".to_string(), Value::Object(paths));
+
+    for key in ["security", "tags", "externalDocs"].iter() {
+        if let Some(value) = swagger.remove(*key) {
+            spec.insert(key.to_string(), value);
+        }
+    }
+    for (key, value) in swagger.into_iter().filter(|(key, _)| key.starts_with("x-")) {
+        spec.insert(key, value);
+    }
+
+    let mut spec = Value::Object(spec);
+    rewrite_references(&mut spec);
+    Ok(spec)
+}
+
+fn take_object(map: &mut Map<String, Value>, key: &str) -> Map<String, Value> {
+    match map.remove(key) {
+        Some(Value::Object(object)) => object,
+        _ => Map::new(),
+    }
+}
+
+fn media_types(value: Option<&Value>) -> Vec<String> {
+    value
+        .and_then(Value::as_array)
+        .map(|types| {
+            types
+                .iter()
+                .filter_map(Value::as_str)
+                .map(String::from)
+                .collect()
+        })
+        .filter(|types: &Vec<String>| !types.is_empty())
+        .unwrap_or_else(|| vec!["application/json".to_string()])
+}
+
+/// `type: file`, `x-nullable` and string discriminators have different spellings in 3.0
+fn convert_schema(schema: &mut Value) {
+    match schema {
+        Value::Object(object) => {
+            if object.get("type").and_then(Value::as_str) == Some("file") {
+                object.insert("type".to_string(), json!("string"));
+                object.insert("format".to_string(), json!("binary"));
+            }
+            if let Some(nullable) = object.remove("x-nullable") {
+                object.insert("nullable".to_string(), nullable);
+            }
+            if let Some(Value::String(property_name)) = object.get("discriminator").cloned() {
+                object.insert(
+                    "discriminator".to_string(),
+                    json!({ "propertyName": property_name }),
+                );
+            }
+            for value in object.values_mut() {
+                convert_schema(value);
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(convert_schema),
+        _ => {}
+    }
+}
+
+/// move inline type keywords of a parameter or header under `schema`
+fn nest_schema(object: &mut Map<String, Value>) {
+    let mut schema = Map::new();
+    for keyword in SCHEMA_KEYWORDS.iter() {
+        if let Some(value) = object.remove(*keyword) {
+            schema.insert(keyword.to_string(), value);
+        }
+    }
+    object.remove("collectionFormat");
+    if !schema.is_empty() {
+        let mut schema = Value::Object(schema);
+        convert_schema(&mut schema);
+        object.insert("schema".to_string(), schema);
+    }
+}
+
+/// whether a parameter, or the global one it references, is `in: body` or `in: formData`
+fn is_body(parameter: &Value, global_parameters: &Map<String, Value>) -> bool {
+    let resolved = match parameter.get("$ref").and_then(Value::as_str) {
+        Some(reference) => global_parameters.get(reference.trim_start_matches("#/parameters/")),
+        None => Some(parameter),
+    };
+    matches!(
+        resolved
+            .and_then(|resolved| resolved.get("in"))
+            .and_then(Value::as_str),
+        Some("body") | Some("formData")
+    )
+}
+
+/// add path-level parameters to an operation, unless it overrides them by name and location
+fn inherit_parameters(
+    operation: &mut Value,
+    inherited: &[Value],
+    global_parameters: &Map<String, Value>,
+) {
+    let operation = match operation {
+        Value::Object(operation) if !inherited.is_empty() => operation,
+        _ => return,
+    };
+    let resolve = |parameter: &Value| -> (Value, Value) {
+        let resolved = match parameter.get("$ref").and_then(Value::as_str) {
+            Some(reference) => global_parameters
+                .get(reference.trim_start_matches("#/parameters/"))
+                .unwrap_or(parameter),
+            None => parameter,
+        };
+        (resolved["name"].clone(), resolved["in"].clone())
+    };
+    let parameters = operation.entry("parameters").or_insert_with(|| json!([]));
+    if let Value::Array(parameters) = parameters {
+        let own: Vec<(Value, Value)> = parameters.iter().map(resolve).collect();
+        for parameter in inherited {
+            if !own.contains(&resolve(parameter)) {
+                parameters.push(parameter.clone());
+            }
+        }
+    }
+}
+
+fn convert_parameter(mut parameter: Value) -> Value {
+    if let Value::Object(object) = &mut parameter {
+        if !object.contains_key("$ref") {
+            nest_schema(object);
+        }
+    }
+    parameter
+}
+
+/// a 3.0 request body from `in: body` or `in: formData` parameters
+fn request_body(parameters: &[Value], consumes: &[String]) -> Value {
+    let mut body = Map::new();
+    let mut content = Map::new();
+
+    if let Some(parameter) = parameters
+        .iter()
+        .find(|parameter| parameter["in"] == "body")
+    {
+        let mut schema = parameter
+            .get("schema")
+            .cloned()
+            .unwrap_or_else(|| json!({}));
+        convert_schema(&mut schema);
+        for media_type in consumes {
+            content.insert(media_type.clone(), json!({ "schema": schema }));
+        }
+        if let Some(description) = parameter.get("description") {
+            body.insert("description".to_string(), description.clone());
+        }
+        body.insert(
+            "required".to_string(),
+            parameter.get("required").cloned().unwrap_or(json!(false)),
+        );
+    } else {
+        let mut properties = Map::new();
+        let mut required = vec![];
+        for parameter in parameters {
+            let mut property = parameter.clone();
+            if let Value::Object(object) = &mut property {
+                nest_schema(object);
+            }
+            let name = parameter["name"].as_str().unwrap_or("").to_string();
+            if parameter["required"] == true {
+                required.push(json!(name));
+            }
+            properties.insert(
+                name,
+                property.get("schema").cloned().unwrap_or_else(|| json!({})),
+            );
+        }
+        let media_type = if consumes
+            .iter()
+            .any(|media_type| media_type == "multipart/form-data")
+        {
+            "multipart/form-data"
+        } else {
+            "application/x-www-form-urlencoded"
+        };
+        let schema = json!({ "type": "object", "properties": properties, "required": required });
+        content.insert(media_type.to_string(), json!({ "schema": schema }));
+        body.insert("required".to_string(), json!(!required.is_empty()));
+    }
+
+    body.insert("content".to_string(), Value::Object(content));
+    Value::Object(body)
+}
+
+fn convert_response(response: &mut Value, produces: &[String]) {
+    let response = match response {
+        Value::Object(response) if !response.contains_key("$ref") => response,
+        _ => return,
+    };
+    let examples = take_object(response, "examples");
+    if let Some(mut schema) = response.remove("schema") {
+        convert_schema(&mut schema);
+        let mut content = Map::new();
+        for media_type in produces {
+            let mut media = json!({ "schema": schema });
+            if let Some(example) = examples.get(media_type) {
+                media["example"] = example.clone();
+            }
+            content.insert(media_type.clone(), media);
+        }
+        response.insert("content".to_string(), Value::Object(content));
+    }
+    if let Some(Value::Object(headers)) = response.get_mut("headers") {
+        for header in headers.values_mut() {
+            if let Value::Object(header) = header {
+                nest_schema(header);
+            }
+        }
+    }
+    if !response.contains_key("description") {
+        response.insert("description".to_string(), json!(""));
+    }
+}
+
+fn convert_operation(
+    operation: &mut Value,
+    global_parameters: &Map<String, Value>,
+    consumes: &[String],
+    produces: &[String],
+) {
+    let operation = match operation {
+        Value::Object(operation) => operation,
+        _ => return,
+    };
+    let consumes = operation
+        .remove("consumes")
+        .map(|types| media_types(Some(&types)))
+        .unwrap_or_else(|| consumes.to_vec());
+    let produces = operation
+        .remove("produces")
+        .map(|types| media_types(Some(&types)))
+        .unwrap_or_else(|| produces.to_vec());
+    operation.remove("schemes");
+
+    let mut parameters = vec![];
+    let mut body_parameters = vec![];
+    let mut body_reference = None;
+    if let Some(Value::Array(list)) = operation.remove("parameters") {
+        for parameter in list {
+            if !is_body(&parameter, global_parameters) {
+                parameters.push(convert_parameter(parameter));
+            } else if let Some(reference) = parameter.get("$ref").and_then(Value::as_str) {
+                body_reference = Some(reference.to_string());
+            } else {
+                body_parameters.push(parameter);
+            }
+        }
+    }
+    if !parameters.is_empty() {
+        operation.insert("parameters".to_string(), Value::Array(parameters));
+    }
+    if let Some(reference) = body_reference {
+        let name = reference.trim_start_matches("#/parameters/");
+        operation.insert(
+            "requestBody".to_string(),
+            json!({ "$ref": format!("#/components/requestBodies/{}", name) }),
+        );
+    } else if !body_parameters.is_empty() {
+        operation.insert(
+            "requestBody".to_string(),
+            request_body(&body_parameters, &consumes),
+        );
+    }
+
+    if let Some(Value::Object(responses)) = operation.get_mut("responses") {
+        for response in responses.values_mut() {
+            convert_response(response, &produces);
+        }
+    }
+}
+
+fn convert_security_scheme(scheme: &mut Value) {
+    let object = match scheme {
+        Value::Object(object) => object,
+        _ => return,
+    };
+    match object.get("type").and_then(Value::as_str) {
+        Some("basic") => {
+            object.insert("type".to_string(), json!("http"));
+            object.insert("scheme".to_string(), json!("basic"));
+        }
+        Some("oauth2") => {
+            let flow = match object.remove("flow").as_ref().and_then(Value::as_str) {
+                Some("implicit") => "implicit",
+                Some("password") => "password",
+                Some("application") => "clientCredentials",
+                _ => "authorizationCode",
+            };
+            let mut settings = Map::new();
+            for key in ["authorizationUrl", "tokenUrl"].iter() {
+                if let Some(url) = object.remove(*key) {
+                    settings.insert(key.to_string(), url);
+                }
+            }
+            settings.insert(
+                "scopes".to_string(),
+                object.remove("scopes").unwrap_or_else(|| json!({})),
+            );
+            object.insert("flows".to_string(), json!({ flow: settings }));
+        }
+        _ => {}
+    }
+}
+
+fn rewrite_references(value: &mut Value) {
+    match value {
+        Value::Object(object) => {
+            if let Some(Value::String(reference)) = object.get_mut("$ref") {
+                for (from, to) in [
+                    ("#/definitions/", "#/components/schemas/"),
+                    ("#/parameters/", "#/components/parameters/"),
+                    ("#/responses/", "#/components/responses/"),
+                ]
+                .iter()
+                {
+                    if reference.starts_with(from) {
+                        *reference = reference.replacen(from, to, 1);
+                    }
+                }
+            }
+            object.values_mut().for_each(rewrite_references);
+        }
+        Value::Array(items) => items.iter_mut().for_each(rewrite_references),
+        _ => {}
+    }
+}
+
+#[test]
+fn test_swagger_to_openapi() {
+    let swagger: Value = serde_yaml::from_str(
+        r#"
+swagger: "2.0"
+info: { title: petstore, version: 1.0.0 }
+host: petstore.swagger.io
+basePath: /v1
+schemes: [http]
+produces: [application/json]
+paths:
+  /pets/{petId}:
+    put:
+      operationId: updatePet
+      parameters:
+        - { name: petId, in: path, required: true, type: integer, format: int64 }
+        - { name: body, in: body, required: true, schema: { $ref: '#/definitions/Pet' } }
+      responses:
+        '200': { description: ok, schema: { $ref: '#/definitions/Pet' } }
+  /pets/{petId}/photo:
+    post:
+      consumes: [multipart/form-data]
+      parameters:
+        - { name: petId, in: path, required: true, type: integer }
+        - { name: file, in: formData, required: true, type: file }
+      responses:
+        default: { $ref: '#/responses/Error' }
+  /pets:
+    parameters:
+      - $ref: '#/parameters/NewPet'
+      - { name: limit, in: query, type: integer }
+    post:
+      responses:
+        '201': { description: created }
+parameters:
+  NewPet: { name: pet, in: body, required: true, schema: { $ref: '#/definitions/Pet' } }
+responses:
+  Error: { description: error }
+definitions:
+  Pet:
+    type: object
+    discriminator: petType
+    properties:
+      petType: { type: string }
+      name: { type: string, x-nullable: true }
+"#,
+    )
+    .unwrap();
+    assert!(is_swagger(&swagger));
+
+    let spec = swagger_to_openapi(swagger).unwrap();
+    assert_eq!(
+        spec["components"]["schemas"]["Pet"]["discriminator"],
+        json!({ "propertyName": "petType" })
+    );
+    // path-level parameters: the body moves into each operation, the rest stay shared
+    assert_eq!(
+        spec["paths"]["/pets"]["parameters"],
+        json!([{ "name": "limit", "in": "query", "schema": { "type": "integer" } }])
+    );
+    let project = Project::parse_yml(serde_json::from_value(spec).unwrap()).unwrap();
+    assert_eq!(project.info.host, "http://petstore.swagger.io");
+    assert_eq!(project.info.endpoint, "/v1");
+
+    let update = project
+        .requests
+        .iter()
+        .find(|request| request.name == "updatePet")
+        .unwrap();
+    assert_eq!(update.vars[0].variable_type, VariableType::Int64Type);
+    let body = update.body.clone().unwrap();
+    assert_eq!(body.body_type, VariableType::ComplexType("Pet".to_string()));
+    assert!(body.required);
+    assert_eq!(update.response_type, "Pet");
+
+    let upload = project
+        .requests
+        .iter()
+        .find(|request| request.name == "postPetsByPetIdPhoto")
+        .unwrap();
+    let body = upload.body.clone().unwrap();
+    assert_eq!(body.media_type, "multipart/form-data");
+    assert_eq!(
+        body.model.unwrap().vars[0].variable_type,
+        VariableType::BinaryType
+    );
+
+    let add = project
+        .requests
+        .iter()
+        .find(|request| request.name == "postPets")
+        .unwrap();
+    assert_eq!(
+        add.body.clone().unwrap().body_type,
+        VariableType::ComplexType("Pet".to_string())
+    );
+    assert_eq!(
+        add.vars
+            .iter()
+            .map(|var| (var.name.as_str(), var.location.clone()))
+            .collect::<Vec<_>>(),
+        vec![("limit", Some(project::Location::Query))]
+    );
+
+    let swagger = json!({
+        "swagger": "2.0",
+        "info": { "title": "petstore", "version": "1.0.0" },
+        "basePath": "/v1",
+        "paths": {},
+    });
+    let spec = swagger_to_openapi(swagger).unwrap();
+    assert_eq!(spec["servers"], json!([{ "url": "/v1" }]));
+}
@@ -1,3 +1,6 @@
+// `failure_derive` expands to impls nested in anonymous consts.
+#![allow(non_local_definitions)]
+
 use failure::{Error, Fail};
 
 pub type CliResult<T> = Result<T, Error>;
@@ -1,6 +1,8 @@
 mod args;
+mod bundle;
 mod commands;
 mod config;
+mod convert;
 mod error;
 mod logger;
 mod project;
@@ -12,4 +14,6 @@ mod util;
 pub use self::args::run;
 pub use self::error::CliError;
 pub(crate) use self::error::CliResult;
-pub use project::{Method, Model, Project, Request, Variable, VariableType, request::Method::*};
\ No newline at end of file
+pub use project::{
+    request::Method::*, Constraints, Method, Model, Project, Request, Variable, VariableType,
+};
@@ -0,0 +1,563 @@
+use super::*;
+use serde_json::{json, Map, Value};
+
+impl Project {
+    /// Turn the project back into an OpenAPI 3.0 document, eg. to bootstrap a spec from code.
+    /// Keys are left out, as `openapiv3` has nowhere to keep them; see `declare_keys`.
+    pub fn to_openapi(&self, title: &str, version: &str) -> CliResult<OpenAPI> {
+        let mut schemas = Map::new();
+        for model in self.models.iter() {
+            schemas.insert(model.name.clone(), self.model_schema(model));
+        }
+
+        let mut paths = Map::new();
+        for request in self.requests.iter() {
+            let path_item = paths
+                .entry(request.path.clone())
+                .or_insert_with(|| json!({}));
+            let method = request.method.to_string().to_lowercase();
+            if path_item.get(&method).is_some() {
+                return Err(failure::format_err!(
+                    "Request {} repeats {} {}",
+                    request.name,
+                    request.method,
+                    request.path
+                ));
+            }
+            path_item[method] = self.operation(request);
+        }
+
+        let mut security_schemes = Map::new();
+        for scheme in self.security_schemes.iter() {
+            security_schemes.insert(scheme.name.clone(), security_scheme(&scheme.kind));
+        }
+
+        let servers = if !self.info.servers.is_empty() {
+            serde_json::to_value(&self.info.servers)?
+        } else if !self.info.host.is_empty() {
+            json!([{ "url": format!("{}{}", self.info.host, self.info.endpoint) }])
+        } else {
+            json!([])
+        };
+
+        let spec = json!({
+            "openapi": "3.0.3",
+            "info": { "title": title, "version": version },
+            "servers": servers,
+            "paths": paths,
+            "components": { "schemas": schemas, "securitySchemes": security_schemes },
+        });
+        serde_json::from_value(spec)
+            .map_err(|e| failure::format_err!("Could not build an OpenAPI document: {}", e))
+    }
+
+    /// the schema of a model in `components.schemas`
+    pub(crate) fn model_schema(&self, model: &Model) -> Value {
+        let mut schema = if let Some(union) = &model.union {
+            let variants: Vec<Value> = union
+                .variants
+                .iter()
+                .map(|variant| reference(variant))
+                .collect();
+            let kind = match union.kind {
+                UnionKind::OneOf => "oneOf",
+                UnionKind::AnyOf => "anyOf",
+            };
+            let mut schema = json!({ kind: variants });
+            if let Some(discriminator) = &union.discriminator {
+                schema["discriminator"] = json!({
+                    "propertyName": discriminator.property_name,
+                    "mapping": discriminator.mapping,
+                });
+            }
+            schema
+        } else if let Some(values) = &model.enumeration {
+            json!({ "type": "string", "enum": values })
+        } else {
+            // variables of the parent are flattened in, so only declare the model's own
+            match model.extends.as_ref() {
+                Some(parent) => {
+                    let inherited: Vec<&String> = self
+                        .models
+                        .iter()
+                        .find(|model| &model.name == parent)
+                        .map(|parent| parent.vars.iter().map(|var| &var.name).collect())
+                        .unwrap_or_default();
+                    let own: Vec<&Variable> = model
+                        .vars
+                        .iter()
+                        .map(|var| &**var)
+                        .filter(|var| !inherited.contains(&&var.name))
+                        .collect();
+                    json!({ "allOf": [reference(parent), object_schema(&own)] })
+                }
+                None => object_schema(&model.vars.iter().map(|var| &**var).collect::<Vec<_>>()),
+            }
+        };
+
+        if let Some(description) = &model.description {
+            schema["description"] = json!(description);
+        }
+        if let Some(example) = &model.example {
+            schema["example"] = serde_json::from_str(example).unwrap_or_else(|_| json!(example));
+        }
+        if model.deprecated {
+            schema["deprecated"] = json!(true);
+        }
+        schema
+    }
+
+    /// the operation a request is under its route and method
+    pub(crate) fn operation(&self, request: &Request) -> Value {
+        let mut operation = json!({ "operationId": request.name });
+        if let Some(summary) = &request.summary {
+            operation["summary"] = json!(summary);
+        }
+        if let Some(description) = &request.description {
+            operation["description"] = json!(description);
+        }
+        if request.deprecated {
+            operation["deprecated"] = json!(true);
+        }
+        if !request.tags.is_empty() {
+            operation["tags"] = json!(request.tags);
+        }
+        if !request.security.is_empty() {
+            operation["security"] = json!(request.security);
+        }
+
+        let parameters: Vec<Value> = request
+            .vars
+            .iter()
+            .map(|var| parameter(var, &request.path))
+            .collect();
+        if !parameters.is_empty() {
+            operation["parameters"] = json!(parameters);
+        }
+
+        if let Some(body) = &request.body {
+            let schema = match &body.model {
+                Some(model) => self.model_schema(model),
+                None => type_schema(&body.body_type),
+            };
+            operation["requestBody"] = json!({
+                "required": body.required,
+                "content": { body.media_type.clone(): { "schema": schema } },
+            });
+        }
+
+        let mut responses = Map::new();
+        for response in request.responses.iter() {
+            responses.insert(response.status.clone(), status_response(response));
+        }
+        if responses.is_empty() {
+            // adaptors only report the simple form
+            responses.insert(
+                "200".to_string(),
+                self.simple_response(&request.response_type),
+            );
+        }
+        operation["responses"] = Value::Object(responses);
+        operation
+    }
+
+    /// a response from `response_type`, eg. `[Pet]` for an array of `Pet`
+    fn simple_response(&self, response_type: &str) -> Value {
+        let (name, is_array) = match response_type
+            .strip_prefix('[')
+            .and_then(|name| name.strip_suffix(']'))
+        {
+            Some(name) => (name, true),
+            None => (response_type, false),
+        };
+        if !self.models.iter().any(|model| model.name == name) {
+            return json!({ "description": "" });
+        }
+        let schema = if is_array {
+            json!({ "type": "array", "items": reference(name) })
+        } else {
+            reference(name)
+        };
+        json!({ "description": "", "content": { "application/json": { "schema": schema } } })
+    }
+}
+
+fn reference(name: &str) -> Value {
+    json!({ "$ref": format!("#/components/schemas/{}", name) })
+}
+
+fn object_schema(vars: &[&Variable]) -> Value {
+    let mut properties = Map::new();
+    let mut required = vec![];
+    for var in vars {
+        properties.insert(var.name.clone(), variable_schema(var));
+        if !var.optional {
+            required.push(var.name.clone());
+        }
+    }
+    let mut schema = json!({ "type": "object", "properties": properties });
+    if !required.is_empty() {
+        schema["required"] = json!(required);
+    }
+    schema
+}
+
+fn type_schema(variable_type: &VariableType) -> Value {
+    match variable_type {
+        VariableType::StringType => json!({ "type": "string" }),
+        VariableType::IntType => json!({ "type": "integer" }),
+        VariableType::BoolType => json!({ "type": "boolean" }),
+        VariableType::FloatType => json!({ "type": "number" }),
+        VariableType::Int64Type => json!({ "type": "integer", "format": "int64" }),
+        VariableType::DoubleType => json!({ "type": "number", "format": "double" }),
+        VariableType::DateType => json!({ "type": "string", "format": "date" }),
+        VariableType::DateTimeType => json!({ "type": "string", "format": "date-time" }),
+        VariableType::UuidType => json!({ "type": "string", "format": "uuid" }),
+        VariableType::ByteType => json!({ "type": "string", "format": "byte" }),
+        VariableType::BinaryType => json!({ "type": "string", "format": "binary" }),
+        VariableType::ComplexType(name) => reference(name),
+        VariableType::EnumType(values) => json!({ "type": "string", "enum": values }),
+        VariableType::MapType(_, value_type) => match &**value_type {
+            VariableType::AnyType => json!({ "type": "object", "additionalProperties": true }),
+            value_type => {
+                json!({ "type": "object", "additionalProperties": type_schema(value_type) })
+            }
+        },
+        VariableType::AnyType => json!({}),
+        VariableType::ArrayType(item_type) => {
+            json!({ "type": "array", "items": type_schema(item_type) })
+        }
+    }
+}
+
+/// the schema of a variable, with its documentation, flags and constraints
+fn variable_schema(var: &Variable) -> Value {
+    let mut extra = Map::new();
+    if let Some(description) = &var.description {
+        extra.insert("description".to_string(), json!(description));
+    }
+    if let Some(value) = &var.value {
+        extra.insert(
+            "default".to_string(),
+            literal_value(value, &var.variable_type),
+        );
+    }
+    if let Some(example) = &var.example {
+        extra.insert(
+            "example".to_string(),
+            literal_value(example, &var.variable_type),
+        );
+    }
+    for (flag, set) in [
+        ("nullable", var.nullable),
+        ("readOnly", var.read_only),
+        ("writeOnly", var.write_only),
+        ("deprecated", var.deprecated),
+    ]
+    .iter()
+    {
+        if *set {
+            extra.insert(flag.to_string(), json!(true));
+        }
+    }
+
+    let constraints = &var.constraints;
+    let numbers = [
+        ("minimum", constraints.minimum),
+        ("maximum", constraints.maximum),
+        ("multipleOf", constraints.multiple_of),
+    ];
+    for (keyword, value) in numbers.iter() {
+        if let Some(value) = value {
+            extra.insert(keyword.to_string(), number(*value));
+        }
+    }
+    let counts = [
+        ("minLength", constraints.min_length),
+        ("maxLength", constraints.max_length),
+        ("minItems", constraints.min_items),
+        ("maxItems", constraints.max_items),
+    ];
+    for (keyword, value) in counts.iter() {
+        if let Some(value) = value {
+            extra.insert(keyword.to_string(), json!(value));
+        }
+    }
+    for (flag, set) in [
+        ("exclusiveMinimum", constraints.exclusive_minimum),
+        ("exclusiveMaximum", constraints.exclusive_maximum),
+        ("uniqueItems", constraints.unique_items),
+    ]
+    .iter()
+    {
+        if *set {
+            extra.insert(flag.to_string(), json!(true));
+        }
+    }
+    if let Some(pattern) = &constraints.pattern {
+        extra.insert("pattern".to_string(), json!(pattern));
+    }
+
+    let schema = type_schema(&var.variable_type);
+    if extra.is_empty() {
+        return schema;
+    }
+    match schema {
+        // nothing may sit beside a `$ref` in 3.0
+        schema @ Value::Object(_) if schema.get("$ref").is_some() => {
+            extra.insert("allOf".to_string(), json!([schema]));
+            Value::Object(extra)
+        }
+        Value::Object(mut schema) => {
+            schema.extend(extra);
+            Value::Object(schema)
+        }
+        schema => schema,
+    }
+}
+
+/// a JSON number, as an integer when it is one so it fits `integer` schemas
+fn number(value: f64) -> Value {
+    if value.fract() == 0.0 && value.abs() < i64::MAX as f64 {
+        json!(value as i64)
+    } else {
+        json!(value)
+    }
+}
+
+/// undo `literal`: string-like types keep the text, anything else is parsed as JSON
+fn literal_value(literal: &str, variable_type: &VariableType) -> Value {
+    match variable_type {
+        VariableType::StringType
+        | VariableType::DateType
+        | VariableType::DateTimeType
+        | VariableType::UuidType
+        | VariableType::ByteType
+        | VariableType::BinaryType
+        | VariableType::EnumType(_) => json!(literal),
+        _ => serde_json::from_str(literal).unwrap_or_else(|_| json!(literal)),
+    }
+}
+
+/// a variable without a location is in the path when `path` names it, and in the query otherwise
+fn parameter(var: &Variable, path: &str) -> Value {
+    let location = var.location.clone().unwrap_or_else(|| {
+        if path.contains(&format!("{{{}}}", var.name)) {
+            Location::Path
+        } else {
+            Location::Query
+        }
+    });
+    let mut parameter = json!({
+        "name": var.name,
+        "in": location,
+        "required": !var.optional || location == Location::Path,
+        "schema": variable_schema(var),
+    });
+    if let Some(description) = &var.description {
+        parameter["description"] = json!(description);
+    }
+    if var.deprecated {
+        parameter["deprecated"] = json!(true);
+    }
+    parameter
+}
+
+fn status_response(response: &StatusResponse) -> Value {
+    let mut emitted = json!({ "description": response.description.clone().unwrap_or_default() });
+    if let Some(response_type) = &response.response_type {
+        let media_type = response
+            .media_type
+            .clone()
+            .unwrap_or_else(|| "application/json".to_string());
+        emitted["content"] = json!({ media_type: { "schema": type_schema(response_type) } });
+    }
+    if !response.headers.is_empty() {
+        let mut headers = Map::new();
+        for header in response.headers.iter() {
+            let mut emitted =
+                json!({ "required": !header.optional, "schema": variable_schema(header) });
+            if let Some(description) = &header.description {
+                emitted["description"] = json!(description);
+            }
+            headers.insert(header.name.clone(), emitted);
+        }
+        emitted["headers"] = Value::Object(headers);
+    }
+    emitted
+}
+
+fn security_scheme(kind: &security::SecurityKind) -> Value {
+    match kind {
+        security::SecurityKind::ApiKey { location, name } => {
+            json!({ "type": "apiKey", "in": location, "name": name })
+        }
+        security::SecurityKind::Http {
+            scheme,
+            bearer_format,
+        } => {
+            let mut emitted = json!({ "type": "http", "scheme": scheme });
+            if let Some(bearer_format) = bearer_format {
+                emitted["bearerFormat"] = json!(bearer_format);
+            }
+            emitted
+        }
+        security::SecurityKind::OAuth2 { flows } => {
+            let mut emitted = Map::new();
+            for flow in flows {
+                let mut settings = json!({ "scopes": flow.scopes });
+                for (key, url) in [
+                    ("authorizationUrl", &flow.authorization_url),
+                    ("tokenUrl", &flow.token_url),
+                    ("refreshUrl", &flow.refresh_url),
+                ]
+                .iter()
+                {
+                    if let Some(url) = url {
+                        settings[*key] = json!(url);
+                    }
+                }
+                emitted.insert(
+                    serde_json::to_value(&flow.kind)
+                        .ok()
+                        .and_then(|kind| kind.as_str().map(String::from))
+                        .unwrap_or_default(),
+                    settings,
+                );
+            }
+            json!({ "type": "oauth2", "flows": emitted })
+        }
+        security::SecurityKind::OpenIdConnect { url } => {
+            json!({ "type": "openIdConnect", "openIdConnectUrl": url })
+        }
+    }
+}
+
+#[test]
+fn test_to_openapi() {
+    let spec = r#"
+openapi: 3.0.0
+info: { title: petstore, version: 1.0.0 }
+servers:
+  - url: 'https://{region}.example.com/v1'
+    variables:
+      region: { default: eu, enum: [eu, us] }
+paths:
+  /pets/{petId}:
+    get:
+      operationId: getPet
+      tags: [pets]
+      security: [{ apiKey: [] }]
+      parameters:
+        - { name: petId, in: path, required: true, schema: { type: integer, format: int64, minimum: 1 } }
+        - { name: fields, in: query, description: fields to return, schema: { type: array, items: { type: string } } }
+      responses:
+        '200':
+          description: the pet
+          headers:
+            X-Rate-Limit: { required: true, schema: { type: integer } }
+          content:
+            application/json: { schema: { $ref: '#/components/schemas/Pet' } }
+        default: { description: error }
+  /pets/{petId}/photo:
+    get:
+      operationId: getPetPhoto
+      parameters:
+        - { name: petId, in: path, required: true, schema: { type: integer, format: int64 } }
+      responses:
+        '200': { description: the photo }
+  /pets:
+    post:
+      operationId: addPet
+      summary: add a pet
+      requestBody:
+        required: true
+        content:
+          application/json:
+            schema:
+              type: object
+              required: [name]
+              properties:
+                name: { type: string, maxLength: 20 }
+      responses:
+        '201':
+          description: created
+          content:
+            application/json: { schema: { type: array, items: { $ref: '#/components/schemas/Pet' } } }
+components:
+  securitySchemes:
+    apiKey: { type: apiKey, in: header, name: X-Api-Key }
+    oauth:
+      type: oauth2
+      flows:
+        clientCredentials: { tokenUrl: 'https://example.com/token', scopes: { read: read pets } }
+  schemas:
+    Animal:
+      type: object
+      required: [name]
+      properties:
+        name: { type: string, description: what to call it, example: rex }
+    Pet:
+      description: a pet
+      allOf:
+        - $ref: '#/components/schemas/Animal'
+        - type: object
+          properties:
+            owner: { allOf: [{ $ref: '#/components/schemas/Animal' }], nullable: true }
+            size: { $ref: '#/components/schemas/Size' }
+            weight: { type: number, format: double, default: 1.5, exclusiveMinimum: true, minimum: 0 }
+            labels: { type: object, additionalProperties: { type: string } }
+    Size: { type: string, enum: [small, large] }
+    Cat: { type: object, properties: { lives: { type: integer } } }
+    Dog: { type: object, properties: { barks: { type: boolean, readOnly: true } } }
+    Friend:
+      oneOf: [{ $ref: '#/components/schemas/Cat' }, { $ref: '#/components/schemas/Dog' }]
+      discriminator: { propertyName: kind }
+"#;
+    let project = Project::parse_yml(serde_yaml::from_str(spec).unwrap()).unwrap();
+    let emitted = project.to_openapi("petstore", "1.0.0").unwrap();
+    assert_eq!(emitted.info.title, "petstore");
+    assert_eq!(
+        emitted.paths.keys().collect::<Vec<_>>(),
+        vec!["/pets/{petId}", "/pets/{petId}/photo", "/pets"]
+    );
+
+    let reparsed = Project::parse_yml(emitted).unwrap();
+    let sorted = |models: &[Model]| {
+        let mut models = models.to_vec();
+        models.sort_by(|a, b| a.name.cmp(&b.name));
+        models
+    };
+    assert_eq!(sorted(&reparsed.models), sorted(&project.models));
+    for request in project.requests.iter() {
+        assert_eq!(
+            reparsed
+                .requests
+                .iter()
+                .find(|reparsed| reparsed.name == request.name),
+            Some(request)
+        );
+    }
+    assert_eq!(reparsed.security_schemes, project.security_schemes);
+    assert_eq!(reparsed.info.servers, project.info.servers);
+
+    // as an adaptor reports a request, with only the simple form of its response
+    let mut project = reparsed;
+    project.requests = vec![Request {
+        name: "listCats".to_string(),
+        path: "/cats".to_string(),
+        vars: vec![],
+        method: Method::Get_,
+        response_type: "[Cat]".to_string(),
+        responses: vec![],
+        body: None,
+        summary: None,
+        description: None,
+        deprecated: false,
+        tags: vec![],
+        security: vec![],
+    }];
+    let reparsed = Project::parse_yml(project.to_openapi("petstore", "1.0.0").unwrap()).unwrap();
+    assert_eq!(reparsed.requests[0].path, "/cats");
+    assert_eq!(reparsed.requests[0].response_type, "[Cat]");
+}
@@ -0,0 +1,219 @@
+use super::*;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// primary key of a model, from `x-cdd-key`/`x-keys` on its schema or `default_key` in config.yml
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum Key {
+    /// one of the model's properties, eg. `x-cdd-key: id`
+    Name(String),
+    /// a composite key of the model's properties, eg. `x-keys: [owner, name]`
+    Names(Vec<String>),
+    /// a key field, added to the model unless it already has one by that name,
+    /// eg. `x-cdd-key: { name: id, type: string, format: uuid }`
+    Field {
+        name: String,
+        #[serde(rename = "type")]
+        key_type: String,
+        #[serde(default)]
+        format: Option<String>,
+    },
+}
+
+/// keys declared on `components.schemas` of a 3.0 spec, by schema name.
+/// `openapiv3` drops vendor extensions, so they are read before it parses the spec.
+pub fn declared_keys(spec: &Value) -> CliResult<BTreeMap<String, Key>> {
+    let mut keys = BTreeMap::new();
+    let schemas = match spec
+        .pointer("/components/schemas")
+        .and_then(Value::as_object)
+    {
+        Some(schemas) => schemas,
+        None => return Ok(keys),
+    };
+    for (name, schema) in schemas {
+        let extension = schema.get("x-cdd-key").or_else(|| schema.get("x-keys"));
+        if let Some(extension) = extension {
+            let key = serde_json::from_value(extension.clone())
+                .map_err(|e| failure::format_err!("Invalid key on {}: {}", name, e))?;
+            keys.insert(name.clone(), key);
+        }
+    }
+    Ok(keys)
+}
+
+/// the inverse of `declared_keys`: add `x-cdd-key` to the schemas of models with keys
+pub(crate) fn declare_keys(spec: &mut Value, models: &[Model]) {
+    for model in models.iter().filter(|model| !model.keys.is_empty()) {
+        if let Some(Value::Object(schema)) =
+            spec.pointer_mut(&format!("/components/schemas/{}", model.name))
+        {
+            let key = match model.keys.as_slice() {
+                [name] => serde_json::json!(name),
+                names => serde_json::json!(names),
+            };
+            schema.insert("x-cdd-key".to_string(), key);
+        }
+    }
+}
+
+impl Project {
+    /// set the keys of object models, falling back to `default` for models that declare none.
+    /// the default only applies to the named `schemas`, not to models hoisted from inline
+    /// schemas, and a default naming properties only to models that have them.
+    pub fn apply_keys(
+        &mut self,
+        declared: &BTreeMap<String, Key>,
+        default: Option<&Key>,
+        schemas: &[String],
+    ) -> CliResult<()> {
+        for model in self.models.iter_mut().filter(|model| model.is_object()) {
+            let (key, is_default) = match (declared.get(&model.name), default) {
+                (Some(key), _) => (key, false),
+                (None, Some(key)) if schemas.contains(&model.name) => (key, true),
+                (None, _) => continue,
+            };
+
+            let names = match key {
+                Key::Name(name) => vec![name.clone()],
+                Key::Names(names) => names.clone(),
+                Key::Field {
+                    name,
+                    key_type,
+                    format,
+                } => {
+                    if !model.vars.iter().any(|var| var.name == *name) {
+                        let schema: openapiv3::Schema = serde_json::from_value(
+                            serde_json::json!({ "type": key_type, "format": format }),
+                        )
+                        .map_err(|e| {
+                            failure::format_err!("Invalid key on {}: {}", model.name, e)
+                        })?;
+                        let variable_type = match &schema.schema_kind {
+                            SchemaKind::Type(schema_type) => scalar_type(schema_type),
+                            _ => None,
+                        }
+                        .ok_or_else(|| {
+                            failure::format_err!(
+                                "Invalid key on {}: {} is not a scalar type",
+                                model.name,
+                                key_type
+                            )
+                        })?;
+                        model.vars.insert(
+                            0,
+                            Box::new(Variable {
+                                name: name.clone(),
+                                variable_type,
+                                ..Default::default()
+                            }),
+                        );
+                    }
+                    vec![name.clone()]
+                }
+            };
+
+            let missing = names
+                .iter()
+                .find(|name| !model.vars.iter().any(|var| var.name == **name));
+            match missing {
+                Some(_) if is_default => continue,
+                Some(name) => {
+                    return Err(failure::format_err!(
+                        "Key {} of {} is not one of its properties",
+                        name,
+                        model.name
+                    ));
+                }
+                None => model.keys = names,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_keys() {
+    let spec: Value = serde_yaml::from_str(
+        r#"
+openapi: 3.0.0
+info: { title: test, version: 0.0.1 }
+paths: {}
+components:
+  schemas:
+    Pet:
+      type: object
+      x-cdd-key: { name: id, type: string, format: uuid }
+      properties:
+        name: { type: string }
+    Tag:
+      type: object
+      x-keys: [owner, label]
+      properties:
+        owner: { type: integer }
+        label: { type: string }
+    Error:
+      type: object
+      properties:
+        message: { type: string }
+    Owner:
+      type: object
+      properties:
+        address: { type: object, properties: { street: { type: string } } }
+"#,
+    )
+    .unwrap();
+    let declared = declared_keys(&spec).unwrap();
+    let schemas: Vec<String> = spec["components"]["schemas"]
+        .as_object()
+        .unwrap()
+        .keys()
+        .cloned()
+        .collect();
+    let parse = || Project::parse_yml(serde_json::from_value(spec.clone()).unwrap()).unwrap();
+    let mut project = parse();
+    project
+        .apply_keys(&declared, Some(&Key::Name("id".to_string())), &schemas)
+        .unwrap();
+
+    let model = |name: &str| {
+        project
+            .models
+            .iter()
+            .find(|model| model.name == name)
+            .unwrap()
+    };
+    assert_eq!(model("Pet").keys, vec!["id"]);
+    assert_eq!(model("Pet").vars[0].variable_type, VariableType::UuidType);
+    assert_eq!(model("Tag").keys, vec!["owner", "label"]);
+    assert!(model("Error").keys.is_empty());
+    assert_eq!(model("Error").vars.len(), 1);
+
+    // a default key field is only added to named schemas, not to `OwnerAddress`
+    let mut project = parse();
+    let id = Key::Field {
+        name: "id".to_string(),
+        key_type: "integer".to_string(),
+        format: None,
+    };
+    project.apply_keys(&declared, Some(&id), &schemas).unwrap();
+    let model = |name: &str| {
+        project
+            .models
+            .iter()
+            .find(|model| model.name == name)
+            .unwrap()
+    };
+    assert_eq!(model("Owner").keys, vec!["id"]);
+    assert!(model("OwnerAddress").keys.is_empty());
+    assert_eq!(model("OwnerAddress").vars.len(), 1);
+
+    let mut declared = BTreeMap::new();
+    declared.insert("Error".to_string(), Key::Name("code".to_string()));
+    let err = project.apply_keys(&declared, None, &schemas).unwrap_err();
+    assert_eq!(
+        format!("{}", err),
+        "Key code of Error is not one of its properties"
+    );
+}
@@ -0,0 +1,339 @@
+use super::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::path::Path;
+
+/// models and requests of a service as of its last sync, kept in `.cdd/state/<service>.json`
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct SyncState {
+    /// what the spec held
+    pub spec: Snapshot,
+    /// what the service's adaptor reported right after the sync, which may leave out
+    /// details its language has no place for
+    pub code: Snapshot,
+}
+
+#[derive(Debug, Default, PartialEq, Clone, Deserialize, Serialize)]
+pub struct Snapshot {
+    pub models: Vec<Model>,
+    pub requests: Vec<Request>,
+}
+
+/// which side of a sync something happened on
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Side {
+    Spec,
+    Code,
+}
+
+/// how a model or request has moved on since the last sync
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Change {
+    Added(Side),
+    Removed(Side),
+    Changed(Side),
+    /// changed differently on both sides, or changed on one and removed on the other
+    Conflict,
+}
+
+impl Change {
+    /// the side that made the change, unless both did
+    pub fn side(self) -> Option<Side> {
+        match self {
+            Change::Added(side) | Change::Removed(side) | Change::Changed(side) => Some(side),
+            Change::Conflict => None,
+        }
+    }
+}
+
+impl std::fmt::Display for Change {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let side = |side: &Side| match side {
+            Side::Spec => "the spec",
+            Side::Code => "code",
+        };
+        match self {
+            Change::Added(on) => write!(f, "added in {}", side(on)),
+            Change::Removed(on) => write!(f, "removed from {}", side(on)),
+            Change::Changed(on) => write!(f, "changed in {}", side(on)),
+            Change::Conflict => write!(f, "changed on both sides"),
+        }
+    }
+}
+
+/// names of the models and requests that changed since the last sync, and how
+#[derive(Debug, Default, PartialEq)]
+pub struct Diff {
+    pub models: Vec<(String, Change)>,
+    pub requests: Vec<(String, Change)>,
+}
+
+impl SyncState {
+    /// the state of a service, or `None` if it was never synced
+    pub fn read(path: &Path) -> CliResult<Option<Self>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| failure::format_err!("Could not read {}: {}", path.display(), e))?;
+        serde_json::from_str(&contents)
+            .map(Some)
+            .map_err(|e| failure::format_err!("Could not parse {}: {}", path.display(), e))
+    }
+
+    pub fn write(&self, path: &Path) -> CliResult<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// the state of a service that was never synced, as if its code was last synced from a spec matching it
+    pub fn unsynced(code: &Snapshot) -> SyncState {
+        SyncState {
+            spec: code.clone(),
+            code: code.clone(),
+        }
+    }
+
+    /// the state after the named models and requests were written from `code` into `spec`,
+    /// so neither side shows them as changed
+    pub fn after_write(
+        &self,
+        spec: &Snapshot,
+        code: &Snapshot,
+        models: &[String],
+        requests: &[String],
+    ) -> SyncState {
+        // `carry` keeps the named items of its first argument, here the written ones
+        let model_name: fn(&Model) -> &String = |model| &model.name;
+        let request_name: fn(&Request) -> &String = |request| &request.name;
+        SyncState {
+            spec: Snapshot {
+                models: carry(&spec.models, self.spec.models.clone(), models, model_name),
+                requests: carry(
+                    &spec.requests,
+                    self.spec.requests.clone(),
+                    requests,
+                    request_name,
+                ),
+            },
+            code: Snapshot {
+                models: carry(&code.models, self.code.models.clone(), models, model_name),
+                requests: carry(
+                    &code.requests,
+                    self.code.requests.clone(),
+                    requests,
+                    request_name,
+                ),
+            },
+        }
+    }
+
+    /// the state to keep after a sync that applied `diff`'s changes from the spec.
+    /// changes made in code, or on both sides, keep their previous state so they are found again next time.
+    pub fn after_sync(&self, diff: &Diff, spec: Snapshot, code: Snapshot) -> SyncState {
+        let pending = |changes: &[(String, Change)]| -> Vec<String> {
+            changes
+                .iter()
+                .filter(|(_, change)| change.side() != Some(Side::Spec))
+                .map(|(name, _)| name.clone())
+                .collect()
+        };
+        let (models, requests) = (pending(&diff.models), pending(&diff.requests));
+        let model_name: fn(&Model) -> &String = |model| &model.name;
+        let request_name: fn(&Request) -> &String = |request| &request.name;
+        SyncState {
+            spec: Snapshot {
+                models: carry(&self.spec.models, spec.models, &models, model_name),
+                requests: carry(&self.spec.requests, spec.requests, &requests, request_name),
+            },
+            code: Snapshot {
+                models: carry(&self.code.models, code.models, &models, model_name),
+                requests: carry(&self.code.requests, code.requests, &requests, request_name),
+            },
+        }
+    }
+
+    /// three-way diff of the spec and code against this state
+    pub fn diff(&self, spec: &Snapshot, code: &Snapshot) -> Diff {
+        Diff {
+            models: diff_by_name(
+                &self.spec.models,
+                &spec.models,
+                &self.code.models,
+                &code.models,
+                |model| &model.name,
+                same_model,
+            ),
+            requests: diff_by_name(
+                &self.spec.requests,
+                &spec.requests,
+                &self.code.requests,
+                &code.requests,
+                |request| &request.name,
+                Request::eq,
+            ),
+        }
+    }
+}
+
+fn diff_by_name<T: PartialEq>(
+    base_spec: &[T],
+    spec: &[T],
+    base_code: &[T],
+    code: &[T],
+    name: fn(&T) -> &String,
+    same: fn(&T, &T) -> bool,
+) -> Vec<(String, Change)> {
+    fn find<'a, T>(items: &'a [T], item_name: &str, name: fn(&T) -> &String) -> Option<&'a T> {
+        items.iter().find(|item| name(item) == item_name)
+    }
+    let names: BTreeSet<&String> = base_spec
+        .iter()
+        .chain(spec)
+        .chain(base_code)
+        .chain(code)
+        .map(name)
+        .collect();
+    names
+        .into_iter()
+        .filter_map(|item_name| {
+            let (spec, code) = (find(spec, item_name, name), find(code, item_name, name));
+            let change = match (
+                side_change(find(base_spec, item_name, name), spec, Side::Spec),
+                side_change(find(base_code, item_name, name), code, Side::Code),
+            ) {
+                (None, None) => None,
+                (Some(change), None) | (None, Some(change)) => Some(change),
+                (Some(Change::Removed(_)), Some(Change::Removed(_))) => None,
+                // both sides made the same change
+                (Some(_), Some(_))
+                    if spec.zip(code).is_some_and(|(spec, code)| same(spec, code)) =>
+                {
+                    None
+                }
+                (Some(_), Some(_)) => Some(Change::Conflict),
+            };
+            change.map(|change| (item_name.clone(), change))
+        })
+        .collect()
+}
+
+/// `current`, except for `pending` names which stay as they were in `previous`
+fn carry<T: Clone>(
+    previous: &[T],
+    current: Vec<T>,
+    pending: &[String],
+    name: fn(&T) -> &String,
+) -> Vec<T> {
+    current
+        .into_iter()
+        .filter(|item| !pending.contains(name(item)))
+        .chain(
+            previous
+                .iter()
+                .filter(|item| pending.contains(name(item)))
+                .cloned(),
+        )
+        .collect()
+}
+
+fn side_change<T: PartialEq>(base: Option<&T>, now: Option<&T>, side: Side) -> Option<Change> {
+    match (base, now) {
+        (None, Some(_)) => Some(Change::Added(side)),
+        (Some(_), None) => Some(Change::Removed(side)),
+        (Some(base), Some(now)) if base != now => Some(Change::Changed(side)),
+        _ => None,
+    }
+}
+
+/// whether a model from code is the spec's, given code doesn't carry keys
+pub(crate) fn same_model(spec: &Model, code: &Model) -> bool {
+    code == spec
+        || (code.keys.is_empty()
+            && Model {
+                keys: spec.keys.clone(),
+                ..code.clone()
+            } == *spec)
+}
+
+#[test]
+fn test_diff() {
+    let model = |name: &str, vars: &[&str]| Model {
+        name: name.to_string(),
+        vars: vars
+            .iter()
+            .map(|var| {
+                Box::new(Variable {
+                    name: var.to_string(),
+                    ..Default::default()
+                })
+            })
+            .collect(),
+        ..Default::default()
+    };
+    let state = SyncState {
+        spec: Snapshot {
+            models: vec![
+                model("Pet", &["name"]),
+                model("Toy", &[]),
+                model("Tag", &[]),
+                model("Owner", &[]),
+            ],
+            requests: vec![],
+        },
+        code: Snapshot {
+            models: vec![
+                model("Pet", &["name"]),
+                model("Toy", &[]),
+                model("Tag", &[]),
+                model("Owner", &[]),
+            ],
+            requests: vec![],
+        },
+    };
+    let spec = Snapshot {
+        models: vec![
+            model("Pet", &["name", "age"]),
+            model("Tag", &["label"]),
+            model("Owner", &["name"]),
+            model("Store", &[]),
+        ],
+        requests: vec![],
+    };
+    let code = Snapshot {
+        models: vec![
+            model("Pet", &["name"]),
+            model("Toy", &[]),
+            model("Tag", &["colour"]),
+            model("Owner", &["name"]),
+            model("Order", &[]),
+        ],
+        requests: vec![],
+    };
+
+    let diff = state.diff(&spec, &code);
+    assert_eq!(
+        diff.models,
+        vec![
+            ("Order".to_string(), Change::Added(Side::Code)),
+            ("Pet".to_string(), Change::Changed(Side::Spec)),
+            ("Store".to_string(), Change::Added(Side::Spec)),
+            ("Tag".to_string(), Change::Conflict),
+            ("Toy".to_string(), Change::Removed(Side::Spec)),
+        ]
+    );
+    assert!(diff.requests.is_empty());
+
+    // `Order` and `Tag` keep their old state until the spec has them
+    let state = state.after_sync(&diff, spec.clone(), code.clone());
+    assert_eq!(
+        state.diff(&spec, &code).models,
+        vec![
+            ("Order".to_string(), Change::Added(Side::Code)),
+            ("Tag".to_string(), Change::Conflict)
+        ]
+    );
+}
@@ -1,5 +1,5 @@
 use openapiv3::*;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use url::Url;
 
 pub mod model;
@@ -8,288 +8,1039 @@ pub mod variable;
 pub use variable::*;
 pub mod request;
 pub use request::*;
+pub mod key;
+pub use key::*;
+pub mod emit;
+pub mod merge;
+pub mod security;
+pub mod server;
 
 #[derive(serde::Serialize, serde::Deserialize, Debug)]
 pub struct Project {
     pub info: Info,
     pub models: Vec<Model>,
     pub requests: Vec<Request>,
+    #[serde(default)]
+    pub security_schemes: Vec<security::SecurityScheme>,
 }
 
-#[derive(serde::Serialize, serde::Deserialize, Debug)]
+#[derive(serde::Serialize, serde::Deserialize, Debug, Default)]
 pub struct Info {
+    /// scheme and host of the first server, eg. `https://example.com`
     pub host: String,
+    /// path of the first server, eg. `/v1`
     pub endpoint: String,
+    #[serde(default)]
+    pub servers: Vec<server::Server>,
 }
 
 use crate::error::*;
 
-fn extract_variable_from_openapi(class_name: &str, var_name: &str, schema: openapiv3::Schema, optional: bool) -> CliResult<Variable> {
-    if let openapiv3::SchemaKind::Type(schema_type) = schema.schema_kind {
-        let variable_type = match schema_type {
-            Type::String(_) => VariableType::StringType,
-            Type::Number(_) => VariableType::FloatType,
-            Type::Integer(_) => VariableType::IntType,
-            Type::Array(val) => {
-                let item_type = Project::parse_type(val.items.clone().unbox());
-                VariableType::ArrayType(Box::new(item_type))
+/// last segment of a `$ref`, eg. `#/components/schemas/Pet` -> `Pet`
+fn reference_name(reference: &str) -> String {
+    reference.rsplit('/').next().unwrap_or("").to_string()
+}
+
+/// resolve a `$ref` on a model property against `components.schemas`
+fn resolve_reference(
+    class_name: &str,
+    var_name: &str,
+    reference: &str,
+    components: &Components,
+) -> CliResult<VariableType> {
+    let target = reference_name(reference);
+    match components.schemas.get(&target) {
+        // arrays of models are not emitted as models, so point straight at their items
+        Some(ReferenceOr::Item(Schema {
+            schema_kind:
+                SchemaKind::Type(Type::Array(ArrayType {
+                    items: ReferenceOr::Reference { reference },
+                    ..
+                })),
+            ..
+        })) => Ok(VariableType::ArrayType(Box::new(
+            VariableType::ComplexType(reference_name(reference)),
+        ))),
+        // nor are maps, so point at their values
+        Some(ReferenceOr::Item(schema)) => match map_alias(schema) {
+            Some(object) => {
+                let value_type = map_value_type(&target, object, components)?;
+                Ok(VariableType::MapType(
+                    Box::new(VariableType::StringType),
+                    Box::new(value_type),
+                ))
+            }
+            None => Ok(VariableType::ComplexType(target)),
+        },
+        Some(_) => Ok(VariableType::ComplexType(target)),
+        None => Err(failure::format_err!(
+            "Unresolved reference on {} for {}: {} does not exist in components.schemas",
+            class_name,
+            var_name,
+            reference
+        )),
+    }
+}
+
+/// like `resolve_reference`, for places that have long accepted references outside components.schemas
+fn reference_type(name: &str, reference: &str, components: &Components) -> CliResult<VariableType> {
+    if components.schemas.contains_key(&reference_name(reference)) {
+        resolve_reference(name, "schema", reference, components)
+    } else {
+        Ok(VariableType::ComplexType(reference_name(reference)))
+    }
+}
+
+/// the object of a component that is nothing but a map, eg. `additionalProperties: { type: integer }`
+fn map_alias(schema: &Schema) -> Option<&ObjectType> {
+    match &schema.schema_kind {
+        SchemaKind::Type(Type::Object(object)) if object.properties.is_empty() => {
+            match object.additional_properties {
+                Some(AdditionalProperties::Any(true)) | Some(AdditionalProperties::Schema(_)) => {
+                    Some(object)
+                }
+                _ => None,
             }
-            Type::Boolean {} => VariableType::BoolType,
-            _ => {
+        }
+        _ => None,
+    }
+}
+
+/// values of the map component `name`; inline objects are the model `{name}Value`, see `parse_yml`
+fn map_value_type(
+    name: &str,
+    object: &ObjectType,
+    components: &Components,
+) -> CliResult<VariableType> {
+    let schema = match map_values(object) {
+        None => return Ok(VariableType::AnyType),
+        Some(ReferenceOr::Reference { reference }) if reference_name(&reference) == name => {
             return Err(failure::format_err!(
-                "Unsupported variable type on {} for {}: {:?}", class_name, var_name, schema_type));
+                "Unsupported map {}: its values are the map itself",
+                name
+            ));
+        }
+        Some(ReferenceOr::Reference { reference }) => {
+            return resolve_reference(name, "additionalProperties", &reference, components)
+        }
+        Some(ReferenceOr::Item(schema)) => schema,
+    };
+    Ok(match &schema.schema_kind {
+        SchemaKind::Type(Type::Array(array)) => {
+            VariableType::ArrayType(Box::new(match &array.items {
+                ReferenceOr::Reference { reference } => {
+                    resolve_reference(name, "additionalProperties", reference, components)?
+                }
+                ReferenceOr::Item(items) => match &items.schema_kind {
+                    SchemaKind::Type(items_type) => {
+                        scalar_type(items_type).unwrap_or(VariableType::AnyType)
+                    }
+                    _ => VariableType::AnyType,
+                },
+            }))
+        }
+        SchemaKind::Type(Type::Object(_))
+        | SchemaKind::AllOf { .. }
+        | SchemaKind::OneOf { .. }
+        | SchemaKind::AnyOf { .. } => VariableType::ComplexType(format!("{}Value", name)),
+        SchemaKind::Type(schema_type) => scalar_type(schema_type).unwrap_or(VariableType::AnyType),
+        _ => VariableType::AnyType,
+    })
+}
+
+/// properties of an object schema, including those inherited through `allOf`
+#[derive(Default)]
+struct ObjectProperties {
+    properties: Vec<(String, ReferenceOr<Box<Schema>>)>,
+    required: Vec<String>,
+}
+
+impl ObjectProperties {
+    fn insert(&mut self, name: &str, schema: &ReferenceOr<Box<Schema>>) {
+        // later members win, eg. a child narrowing a property of its parent
+        match self
+            .properties
+            .iter_mut()
+            .find(|(existing, _)| existing == name)
+        {
+            Some(property) => property.1 = schema.clone(),
+            None => self.properties.push((name.to_string(), schema.clone())),
+        }
+    }
+}
+
+fn collect_properties(
+    class_name: &str,
+    schema: &Schema,
+    components: &Components,
+    parents: &mut Vec<String>,
+    collected: &mut ObjectProperties,
+) -> CliResult<()> {
+    let (properties, required) = match &schema.schema_kind {
+        SchemaKind::Type(Type::Object(o)) => (&o.properties, &o.required),
+        // `allOf` members often leave out `type: object`
+        SchemaKind::Any(any) => (&any.properties, &any.required),
+        SchemaKind::AllOf { all_of } => {
+            for member in all_of {
+                match member {
+                    ReferenceOr::Item(member) => {
+                        collect_properties(class_name, member, components, parents, collected)?
+                    }
+                    ReferenceOr::Reference { reference } => {
+                        let parent = reference_name(reference);
+                        if parents.contains(&parent) {
+                            return Err(failure::format_err!(
+                                "Circular allOf reference on {}: {} -> {}",
+                                class_name,
+                                parents.join(" -> "),
+                                parent
+                            ));
+                        }
+                        let parent_schema = match components.schemas.get(&parent) {
+                            Some(ReferenceOr::Item(parent_schema)) => parent_schema,
+                            _ => return Err(failure::format_err!(
+                                "Unresolved allOf reference on {}: {} does not exist in components.schemas",
+                                class_name,
+                                reference
+                            )),
+                        };
+                        parents.push(parent.clone());
+                        collect_properties(&parent, parent_schema, components, parents, collected)?;
+                        parents.pop();
+                    }
+                }
             }
-        };
+            return Ok(());
+        }
+        schema_kind => {
+            return Err(failure::format_err!("Only concrete object types are supported as return types. model was: {}, schema_type was: {:?}", class_name, schema_kind));
+        }
+    };
+
+    for (name, property) in properties {
+        collected.insert(name, property);
+    }
+    for name in required {
+        if !collected.required.contains(name) {
+            collected.required.push(name.clone());
+        }
+    }
+    Ok(())
+}
+
+/// a union of the members, hoisting inline ones into `models` as eg. `PetVariant2`
+fn parse_union(
+    class_name: &str,
+    kind: UnionKind,
+    members: &[ReferenceOr<Schema>],
+    schema_data: &SchemaData,
+    components: &Components,
+    models: &mut Vec<Model>,
+) -> CliResult<Union> {
+    let mut variants = vec![];
+    for (index, member) in members.iter().enumerate() {
+        match member {
+            ReferenceOr::Reference { reference }
+                if components.schemas.contains_key(&reference_name(reference)) =>
+            {
+                variants.push(reference_name(reference));
+            }
+            ReferenceOr::Reference { reference } => {
+                return Err(failure::format_err!(
+                    "Unresolved reference on {}: {} does not exist in components.schemas",
+                    class_name,
+                    reference
+                ));
+            }
+            ReferenceOr::Item(schema) => {
+                let name = format!("{}Variant{}", class_name, index + 1);
+                variants.push(hoist_model(&name, schema.clone(), models, components)?);
+            }
+        }
+    }
+
+    let discriminator = schema_data.discriminator.as_ref().map(|discriminator| {
+        let mut mapping: BTreeMap<String, String> = discriminator
+            .mapping
+            .iter()
+            .map(|(value, target)| (value.clone(), reference_name(target)))
+            .collect();
+        for variant in variants.iter() {
+            if !mapping.values().any(|target| target == variant) {
+                mapping.insert(variant.clone(), variant.clone());
+            }
+        }
+        model::Discriminator {
+            property_name: discriminator.property_name.clone(),
+            mapping,
+        }
+    });
 
-        Ok(Variable {
-            name: var_name.to_string(),
-            optional,
-            value: None,
-            variable_type: variable_type
+    Ok(Union {
+        kind,
+        variants,
+        discriminator,
+    })
+}
+
+/// map a non-container openapi type onto the IR, honouring `enum` and `format`
+fn scalar_type(schema_type: &Type) -> Option<VariableType> {
+    Some(match schema_type {
+        Type::String(string_type) if !string_type.enumeration.is_empty() => {
+            VariableType::EnumType(string_type.enumeration.clone())
+        }
+        Type::String(string_type) => match &string_type.format {
+            VariantOrUnknownOrEmpty::Item(StringFormat::Date) => VariableType::DateType,
+            VariantOrUnknownOrEmpty::Item(StringFormat::DateTime) => VariableType::DateTimeType,
+            VariantOrUnknownOrEmpty::Item(StringFormat::Byte) => VariableType::ByteType,
+            VariantOrUnknownOrEmpty::Item(StringFormat::Binary) => VariableType::BinaryType,
+            VariantOrUnknownOrEmpty::Unknown(format) if format == "uuid" => VariableType::UuidType,
+            // password, email, uri etc. are only hints
+            _ => VariableType::StringType,
+        },
+        Type::Number(number_type) => match number_type.format {
+            VariantOrUnknownOrEmpty::Item(NumberFormat::Double) => VariableType::DoubleType,
+            _ => VariableType::FloatType,
+        },
+        Type::Integer(integer_type) => match integer_type.format {
+            VariantOrUnknownOrEmpty::Item(IntegerFormat::Int64) => VariableType::Int64Type,
+            _ => VariableType::IntType,
+        },
+        Type::Boolean {} => VariableType::BoolType,
+        Type::Object(_) | Type::Array(_) => return None,
+    })
+}
+
+/// the `application/json` entry of a content map if there is one, otherwise the first
+fn preferred_content(content: &Content) -> Option<(&String, &MediaType)> {
+    content
+        .iter()
+        .find(|(media_type, _)| *media_type == "application/json")
+        .or_else(|| content.iter().next())
+}
+
+/// an inline object without fixed properties is a map, eg. `additionalProperties: { type: integer }`
+fn is_map(object: &ObjectType) -> bool {
+    object.properties.is_empty()
+        && object.additional_properties != Some(AdditionalProperties::Any(false))
+}
+
+/// schema of a map's values, `None` when they can be anything
+fn map_values(object: &ObjectType) -> Option<ReferenceOr<Schema>> {
+    match &object.additional_properties {
+        Some(AdditionalProperties::Schema(schema)) => Some(schema.as_ref().clone()),
+        _ => None,
+    }
+}
+
+/// `name`, or a numbered variant if a component or model already has it
+fn unique_model_name(name: &str, models: &[Model], components: &Components) -> String {
+    let mut unique = name.to_string();
+    let mut suffix = 2;
+    while components.schemas.contains_key(&unique)
+        || models.iter().any(|model| model.name == unique)
+    {
+        unique = format!("{}{}", name, suffix);
+        suffix += 1;
+    }
+    unique
+}
+
+/// add an inline object schema to `models`, under `name` or a numbered variant if that is taken
+fn hoist_model(
+    name: &str,
+    schema: Schema,
+    models: &mut Vec<Model>,
+    components: &Components,
+) -> CliResult<String> {
+    let unique = unique_model_name(name, models, components);
+    let model = Project::parse_model(unique.clone(), schema, components, models)?;
+    models.push(model);
+    Ok(unique)
+}
+
+/// a server, checking every `{name}` in its url has a variable
+fn parse_server(server: openapiv3::Server) -> CliResult<server::Server> {
+    let variables: BTreeMap<String, server::ServerVariable> = server
+        .variables
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(name, variable)| {
+            let variable = server::ServerVariable {
+                default: variable.default,
+                enumeration: variable.enumeration,
+                description: variable.description,
+            };
+            (name, variable)
         })
-    } else {
-        Err(failure::format_err!(
-            "Unsupported variable type on {} for {}", class_name, var_name))
+        .collect();
+
+    for name in server
+        .url
+        .split('{')
+        .skip(1)
+        .filter_map(|part| part.split('}').next())
+    {
+        if !variables.contains_key(name) {
+            return Err(failure::format_err!(
+                "Server {} uses {{{}}} which is not one of its variables",
+                server.url,
+                name
+            ));
+        }
+    }
+
+    Ok(server::Server {
+        url: server.url,
+        description: server.description,
+        variables,
+    })
+}
+
+/// a JSON value as a literal for adaptors: strings as they are, anything else as JSON
+fn literal(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::String(string) => string.clone(),
+        value => value.to_string(),
     }
 }
 
-impl Project {
-    pub fn parse_model(name: String, schema: openapiv3::Schema) -> CliResult<Model> {
-
-        if let openapiv3::SchemaKind::Type(schema_type) = schema.schema_kind {
-            // we only support single-type return types right now. (no multiple schema)
-            if let Type::Object(o) = schema_type {
-                // should be an object (will support raw return types later)
-                let mut vars: Vec<Box<Variable>> = vec![];
-                let required_vars: Vec<String> = o.required;
-
-                for (var_name, props) in o.properties {
-                    if let ReferenceOr::Item(schema) = props {
-                        let optional = !required_vars.contains(&var_name);
-                        let variable = extract_variable_from_openapi(&name, &var_name, *schema, optional)?;
-                        vars.push(Box::new(variable));
-                    } else {
-                        return Err(failure::format_err!(
-                            "Reference types for variables are not supported in {} for {}", name, var_name))
-                    }
+/// validation keywords of a schema
+fn constraints(schema_kind: &SchemaKind) -> Constraints {
+    match schema_kind {
+        SchemaKind::Type(Type::Integer(integer)) => Constraints {
+            minimum: integer.minimum.map(|minimum| minimum as f64),
+            exclusive_minimum: integer.exclusive_minimum,
+            maximum: integer.maximum.map(|maximum| maximum as f64),
+            exclusive_maximum: integer.exclusive_maximum,
+            multiple_of: integer.multiple_of.map(|multiple_of| multiple_of as f64),
+            ..Default::default()
+        },
+        SchemaKind::Type(Type::Number(number)) => Constraints {
+            minimum: number.minimum,
+            exclusive_minimum: number.exclusive_minimum,
+            maximum: number.maximum,
+            exclusive_maximum: number.exclusive_maximum,
+            multiple_of: number.multiple_of,
+            ..Default::default()
+        },
+        SchemaKind::Type(Type::String(string)) => Constraints {
+            min_length: string.min_length,
+            max_length: string.max_length,
+            pattern: string.pattern.clone(),
+            ..Default::default()
+        },
+        SchemaKind::Type(Type::Array(array)) => Constraints {
+            min_items: array.min_items,
+            max_items: array.max_items,
+            unique_items: array.unique_items,
+            ..Default::default()
+        },
+        _ => Constraints::default(),
+    }
+}
+
+/// copy a schema's documentation, default and validation onto a variable
+fn annotate(variable: &mut Variable, schema: &openapiv3::Schema) {
+    let data = &schema.schema_data;
+    if variable.description.is_none() {
+        variable.description = data.description.clone();
+    }
+    if variable.example.is_none() {
+        variable.example = data.example.as_ref().map(literal);
+    }
+    variable.value = data.default.as_ref().map(literal);
+    variable.deprecated |= data.deprecated;
+    variable.nullable = data.nullable;
+    variable.read_only = data.read_only;
+    variable.write_only = data.write_only;
+    variable.constraints = constraints(&schema.schema_kind);
+}
+
+/// `allOf` with a lone `$ref`, which is how 3.0 marks a reference as nullable
+fn lone_reference(schema: &openapiv3::Schema) -> Option<&str> {
+    match &schema.schema_kind {
+        SchemaKind::AllOf { all_of: members }
+        | SchemaKind::OneOf { one_of: members }
+        | SchemaKind::AnyOf { any_of: members }
+            if members.len() == 1 =>
+        {
+            match &members[0] {
+                ReferenceOr::Reference { reference } => Some(reference),
+                ReferenceOr::Item(_) => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+fn extract_variable_from_openapi(
+    class_name: &str,
+    var_name: &str,
+    schema: openapiv3::Schema,
+    optional: bool,
+    components: &Components,
+    models: &mut Vec<Model>,
+) -> CliResult<Variable> {
+    // inline objects become models named after their owner, eg. `Pet.owner` -> `PetOwner`
+    let type_name = format!("{}{}", class_name, pascal_case(var_name));
+    let mut variable = Variable {
+        name: var_name.to_string(),
+        optional,
+        location: None,
+        ..Default::default()
+    };
+    annotate(&mut variable, &schema);
+
+    variable.variable_type = match &schema.schema_kind {
+        _ if lone_reference(&schema).is_some() => resolve_reference(
+            class_name,
+            var_name,
+            lone_reference(&schema).unwrap_or_default(),
+            components,
+        )?,
+        SchemaKind::Type(Type::Array(val)) => {
+            let item_type = match val.items.clone().unbox() {
+                ReferenceOr::Reference { reference } => {
+                    resolve_reference(class_name, var_name, &reference, components)?
+                }
+                item => {
+                    Project::parse_type(item, &format!("{}Item", type_name), components, models)?
+                }
+            };
+            VariableType::ArrayType(Box::new(item_type))
+        }
+        SchemaKind::Type(Type::Object(object)) if is_map(object) => {
+            let value_type = match map_values(object) {
+                Some(ReferenceOr::Reference { reference }) => {
+                    resolve_reference(class_name, var_name, &reference, components)?
                 }
+                Some(value) => {
+                    Project::parse_type(value, &format!("{}Value", type_name), components, models)?
+                }
+                None => VariableType::AnyType,
+            };
+            VariableType::MapType(Box::new(VariableType::StringType), Box::new(value_type))
+        }
+        SchemaKind::Type(Type::Object(_))
+        | SchemaKind::AllOf { .. }
+        | SchemaKind::OneOf { .. }
+        | SchemaKind::AnyOf { .. } => {
+            VariableType::ComplexType(hoist_model(&type_name, schema, models, components)?)
+        }
+        SchemaKind::Type(schema_type) => scalar_type(schema_type).ok_or_else(|| {
+            failure::format_err!(
+                "Unsupported variable type on {} for {}: {:?}",
+                class_name,
+                var_name,
+                schema_type
+            )
+        })?,
+        // no type, or several, as 3.1 allows
+        SchemaKind::Any(_) => VariableType::AnyType,
+    };
 
-                vars.push(Box::new(Variable {
-                    name: "id".to_string(),
-                    optional: false,
-                    value: None,
-                    variable_type: VariableType::IntType,
-                }));
+    Ok(variable)
+}
 
-                return Ok(Model { name, vars });
-            } else {
-                return Err(failure::format_err!("Only concrete object types are supported as return types. model was: {}, schema_type was: {:?}", name, schema_type));
+impl Project {
+    /// parse a model, adding any inline objects among its properties to `models`
+    pub fn parse_model(
+        name: String,
+        schema: openapiv3::Schema,
+        components: &Components,
+        models: &mut Vec<Model>,
+    ) -> CliResult<Model> {
+        // the first referenced `allOf` member is treated as the parent class
+        let extends = match &schema.schema_kind {
+            SchemaKind::AllOf { all_of } => all_of.iter().find_map(|member| match member {
+                ReferenceOr::Reference { reference } => Some(reference_name(reference)),
+                ReferenceOr::Item(_) => None,
+            }),
+            SchemaKind::OneOf { one_of } => {
+                let union = parse_union(
+                    &name,
+                    UnionKind::OneOf,
+                    one_of,
+                    &schema.schema_data,
+                    components,
+                    models,
+                )?;
+                return Ok(Model {
+                    name,
+                    union: Some(union),
+                    ..Default::default()
+                });
             }
+            SchemaKind::AnyOf { any_of } => {
+                let union = parse_union(
+                    &name,
+                    UnionKind::AnyOf,
+                    any_of,
+                    &schema.schema_data,
+                    components,
+                    models,
+                )?;
+                return Ok(Model {
+                    name,
+                    union: Some(union),
+                    ..Default::default()
+                });
+            }
+            SchemaKind::Type(Type::String(string_type)) if !string_type.enumeration.is_empty() => {
+                let enumeration = Some(string_type.enumeration.clone());
+                return Ok(Model {
+                    name,
+                    enumeration,
+                    ..Default::default()
+                });
+            }
+            _ => None,
+        };
+
+        let mut collected = ObjectProperties::default();
+        collect_properties(
+            &name,
+            &schema,
+            components,
+            &mut vec![name.clone()],
+            &mut collected,
+        )?;
+
+        let mut vars: Vec<Box<Variable>> = vec![];
+        for (var_name, props) in collected.properties {
+            let optional = !collected.required.contains(&var_name);
+            let variable = match props {
+                ReferenceOr::Item(schema) => extract_variable_from_openapi(
+                    &name, &var_name, *schema, optional, components, models,
+                )?,
+                ReferenceOr::Reference { reference } => Variable {
+                    variable_type: resolve_reference(&name, &var_name, &reference, components)?,
+                    name: var_name,
+                    optional,
+                    location: None,
+                    ..Default::default()
+                },
+            };
+            vars.push(Box::new(variable));
         }
 
-        // class is a child in an inheritance pattern, don't append variables.
+        let data = &schema.schema_data;
         Ok(Model {
-            name, vars: vec![]
+            name,
+            vars,
+            extends,
+            description: data.description.clone(),
+            example: data.example.as_ref().map(|example| example.to_string()),
+            deprecated: data.deprecated,
+            ..Default::default()
         })
     }
 
-    fn parse_parameter_data(data: ParameterData) -> Variable {
+    fn parse_parameter_data(
+        request_name: &str,
+        data: ParameterData,
+        location: Location,
+        components: &Components,
+        models: &mut Vec<Model>,
+    ) -> CliResult<Variable> {
+        let mut variable = Variable {
+            description: data.description,
+            example: data.example.as_ref().map(literal),
+            deprecated: data.deprecated.unwrap_or(false),
+            ..Default::default()
+        };
         match data.format {
             ParameterSchemaOrContent::Schema(reference) => {
-                let variable_type = Project::parse_type(reference);
-                Variable {
-                    name: data.name,
-                    variable_type,
-                    optional: !data.required,
-                    value: None,
+                let type_name = format!("{}{}", capitalise(request_name), pascal_case(&data.name));
+                if let ReferenceOr::Item(schema) = &reference {
+                    annotate(&mut variable, schema);
                 }
+                variable.variable_type =
+                    Project::parse_type(reference, &type_name, components, models)?;
+                variable.optional = !data.required;
             }
             ParameterSchemaOrContent::Content(_content) => {
                 //Need to implement
-                Variable {
-                    name: data.name,
-                    variable_type: VariableType::StringType,
-                    optional: false,
-                    value: None,
-                }
+                variable.variable_type = VariableType::StringType;
+                variable.optional = false;
             }
         }
+        variable.name = data.name;
+        variable.location = Some(location);
+        Ok(variable)
     }
 
-    /// parse response string from openapi
-    fn parse_response(response: ReferenceOr<Response>) -> String {
-        match response {
-            ReferenceOr::Item(response) => response
-                .content
-                .values()
-                .next()
-                .map(|media_type| {
-                    media_type
-                        .schema
-                        .clone()
-                        .map(|schema| match schema {
-                            ReferenceOr::Reference { reference } => {
-                                reference.split('/').last().unwrap_or("").to_string()
-                            }
-                            _ => "".to_string(),
-                        })
-                        .unwrap_or_else(|| "".to_string())
-                })
-                .unwrap_or_else(|| "".to_string()),
+    /// parse a response, following `$ref`s into components.responses and components.headers
+    fn parse_response(
+        request_name: &str,
+        status: String,
+        response: ReferenceOr<openapiv3::Response>,
+        components: &Components,
+        models: &mut Vec<Model>,
+    ) -> CliResult<StatusResponse> {
+        let response = match response {
+            ReferenceOr::Item(response) => response,
             ReferenceOr::Reference { reference } => {
-                reference.split('/').last().unwrap_or("").to_string()
+                match components.responses.get(&reference_name(&reference)) {
+                    Some(ReferenceOr::Item(response)) => response.clone(),
+                    _ => {
+                        return Err(failure::format_err!(
+                            "Unresolved reference on {} for {}: {} does not exist in components.responses",
+                            request_name,
+                            status,
+                            reference
+                        ));
+                    }
+                }
             }
+        };
+
+        let content = preferred_content(&response.content);
+        let response_type = match content.and_then(|(_, content)| content.schema.clone()) {
+            Some(ReferenceOr::Reference { reference }) => Some(resolve_reference(
+                request_name,
+                &status,
+                &reference,
+                components,
+            )?),
+            Some(schema) => {
+                let type_name = format!(
+                    "{}{}Response",
+                    capitalise(request_name),
+                    pascal_case(&status)
+                );
+                Some(Project::parse_type(schema, &type_name, components, models)?)
+            }
+            None => None,
+        };
+
+        let mut headers = vec![];
+        for (name, header) in response.headers {
+            let header = match header {
+                ReferenceOr::Item(header) => header,
+                ReferenceOr::Reference { reference } => {
+                    match components.headers.get(&reference_name(&reference)) {
+                        Some(ReferenceOr::Item(header)) => header.clone(),
+                        _ => {
+                            return Err(failure::format_err!(
+                                "Unresolved reference on {} for {}: {} does not exist in components.headers",
+                                request_name,
+                                status,
+                                reference
+                            ));
+                        }
+                    }
+                }
+            };
+            let data = ParameterData {
+                name,
+                description: header.description,
+                required: header.required,
+                deprecated: header.deprecated,
+                format: header.format,
+                example: header.example,
+                examples: header.examples,
+            };
+            headers.push(Project::parse_parameter_data(
+                request_name,
+                data,
+                Location::Header,
+                components,
+                models,
+            )?);
         }
+
+        Ok(StatusResponse {
+            status,
+            media_type: content.map(|(media_type, _)| media_type.clone()),
+            response_type,
+            headers,
+            description: Some(response.description).filter(|description| !description.is_empty()),
+        })
     }
 
-    fn parse_type(reference: ReferenceOr<openapiv3::Schema>) -> VariableType {
-        match reference {
+    /// parse a request body, following `$ref`s into components.requestBodies
+    fn parse_request_body(
+        request_name: &str,
+        request_body: ReferenceOr<openapiv3::RequestBody>,
+        components: &Components,
+        models: &mut Vec<Model>,
+    ) -> CliResult<Option<Body>> {
+        let request_body = match request_body {
+            ReferenceOr::Item(request_body) => request_body,
             ReferenceOr::Reference { reference } => {
-                VariableType::ComplexType(reference.split('/').last().unwrap_or("").to_string())
-            }
-            ReferenceOr::Item(schema) => {
-                match &schema.schema_kind {
-                    openapiv3::SchemaKind::Type(t) => {
-                        match t {
-                            Type::String(_) => VariableType::StringType,
-                            Type::Number(_) => VariableType::FloatType,
-                            Type::Integer(_) => VariableType::IntType,
-                            Type::Object(_) => {
-                                VariableType::ComplexType("Need to implement".to_string())
-                            } //Need to implement
-                            Type::Array(val) => {
-                                let item_type = Project::parse_type(val.items.clone().unbox());
-                                VariableType::ArrayType(Box::new(item_type))
-                            }
-                            Type::Boolean {} => VariableType::BoolType,
-                        }
+                match components.request_bodies.get(&reference_name(&reference)) {
+                    Some(ReferenceOr::Item(request_body)) => request_body.clone(),
+                    _ => {
+                        return Err(failure::format_err!(
+                            "Unresolved reference on {}: {} does not exist in components.requestBodies",
+                            request_name,
+                            reference
+                        ));
                     }
-                    _ => VariableType::StringType,
                 }
             }
-        }
+        };
+
+        let (media_type, content) = match preferred_content(&request_body.content) {
+            Some(content) => content,
+            None => return Ok(None),
+        };
+
+        let type_name = format!("{}Body", capitalise(request_name));
+        let (body_type, model) = match content.schema.clone() {
+            // eg. application/octet-stream uploads
+            None => (VariableType::BinaryType, None),
+            Some(ReferenceOr::Reference { reference }) => (
+                resolve_reference(request_name, "body", &reference, components)?,
+                None,
+            ),
+            Some(ReferenceOr::Item(schema)) => match schema.schema_kind {
+                SchemaKind::Type(Type::Object(_)) | SchemaKind::AllOf { .. } => {
+                    let model = Project::parse_model(
+                        unique_model_name(&type_name, models, components),
+                        schema,
+                        components,
+                        models,
+                    )?;
+                    (VariableType::ComplexType(model.name.clone()), Some(model))
+                }
+                _ => (
+                    Project::parse_type(ReferenceOr::Item(schema), &type_name, components, models)?,
+                    None,
+                ),
+            },
+        };
+
+        Ok(Some(Body {
+            media_type: media_type.clone(),
+            body_type,
+            model,
+            required: request_body.required,
+        }))
+    }
+
+    /// map a schema onto the IR, adding inline objects to `models` as `name`
+    fn parse_type(
+        reference: ReferenceOr<openapiv3::Schema>,
+        name: &str,
+        components: &Components,
+        models: &mut Vec<Model>,
+    ) -> CliResult<VariableType> {
+        Ok(match reference {
+            ReferenceOr::Reference { reference } => reference_type(name, &reference, components)?,
+            ReferenceOr::Item(schema) => match &schema.schema_kind {
+                _ if lone_reference(&schema).is_some() => reference_type(
+                    name,
+                    lone_reference(&schema).unwrap_or_default(),
+                    components,
+                )?,
+                SchemaKind::Type(Type::Object(object)) if is_map(object) => {
+                    let value_type = match map_values(object) {
+                        Some(value) => Project::parse_type(
+                            value,
+                            &format!("{}Value", name),
+                            components,
+                            models,
+                        )?,
+                        None => VariableType::AnyType,
+                    };
+                    VariableType::MapType(Box::new(VariableType::StringType), Box::new(value_type))
+                }
+                SchemaKind::Type(Type::Object(_))
+                | SchemaKind::AllOf { .. }
+                | SchemaKind::OneOf { .. }
+                | SchemaKind::AnyOf { .. } => {
+                    VariableType::ComplexType(hoist_model(name, schema, models, components)?)
+                }
+                SchemaKind::Type(Type::Array(val)) => {
+                    let item_type = Project::parse_type(
+                        val.items.clone().unbox(),
+                        &format!("{}Item", name),
+                        components,
+                        models,
+                    )?;
+                    VariableType::ArrayType(Box::new(item_type))
+                }
+                SchemaKind::Type(scalar) => scalar_type(scalar).unwrap_or(VariableType::StringType),
+                _ => VariableType::StringType,
+            },
+        })
     }
 
     pub fn parse_yml(open_api: OpenAPI) -> CliResult<Self> {
         // println!("{}", open_api.info.title);
         //Parse INFO
         let mut project = Project {
-            info: Info {
-                host: "".to_string(),
-                endpoint: "".to_string(),
-            },
+            info: Info::default(),
             models: vec![],
             requests: vec![],
+            security_schemes: vec![],
         };
-        let url = open_api
+        for server in open_api.servers {
+            project.info.servers.push(parse_server(server)?);
+        }
+        let url = project
+            .info
             .servers
             .first()
-            .map(|s| s.url.clone())
-            .unwrap_or_else(|| "".to_string());
+            .map(server::Server::default_url)
+            .unwrap_or_default();
         let res = Url::parse(url.as_str());
 
         if let Ok(url) = res {
             let scheme = url.scheme().to_string();
             let host = url.host_str().unwrap_or("");
-            project.info = Info {
-                host: (scheme + "://" + host),
-                endpoint: url.path().to_string(),
-            }
+            project.info.host = scheme + "://" + host;
+            project.info.endpoint = url.path().to_string();
         };
 
-        let mut arr_types = HashMap::new();
-
         //Parse models
-        let components = open_api.components.unwrap();
-        for (name, schema) in components.schemas {
+        let components = open_api.components.unwrap_or_default();
+        for (name, schema) in components.schemas.clone() {
             if let ReferenceOr::Item(schema) = schema {
-                let mut is_array_type = false;
-                if let openapiv3::SchemaKind::Type(type_) = schema.schema_kind.clone() {
-                    if let Type::Array(array_type) = type_ {
-                        let item_type = Project::parse_type(array_type.items.unbox());
-                        if let VariableType::ComplexType(reference) = item_type {
-                            arr_types.insert(name.clone(), reference);
-                            is_array_type = true
-                        }
-                    };
+                // arrays of models are aliases, resolved wherever they are referenced
+                if let openapiv3::SchemaKind::Type(Type::Array(ArrayType {
+                    items: ReferenceOr::Reference { .. },
+                    ..
+                })) = &schema.schema_kind
+                {
+                    continue;
                 }
-                if !is_array_type {
-                    let model = Project::parse_model(name, schema)?;
-                    project.models.push(model);
+                // and so are maps, though an inline object of their values is a model
+                if let Some(object) = map_alias(&schema) {
+                    if let (
+                        VariableType::ComplexType(value_name),
+                        Some(ReferenceOr::Item(values)),
+                    ) = (
+                        map_value_type(&name, object, &components)?,
+                        map_values(object),
+                    ) {
+                        let model = Project::parse_model(
+                            value_name,
+                            values,
+                            &components,
+                            &mut project.models,
+                        )?;
+                        project.models.push(model);
+                    }
+                    continue;
+                }
+                let model = Project::parse_model(name, schema, &components, &mut project.models)?;
+                project.models.push(model);
+            }
+        }
+
+        for (name, scheme) in components.security_schemes.clone() {
+            match scheme {
+                ReferenceOr::Item(scheme) => project
+                    .security_schemes
+                    .push(security::parse_security_scheme(name, scheme)),
+                ReferenceOr::Reference { reference } => {
+                    return Err(failure::format_err!(
+                        "Unsupported security scheme {}: {} is a reference",
+                        name,
+                        reference
+                    ));
                 }
             }
         }
 
         //Parse Requests
+        let mut routes = HashMap::new();
         for (url_path, path) in open_api.paths {
             if let ReferenceOr::Item(path_item) = path {
                 for (operation, method) in path_item.path_to_request() {
-                    let mut vars: Vec<Box<Variable>> = vec![];
+                    let name = request_name(operation.operation_id.as_deref(), &method, &url_path);
+                    let route = format!("{} {}", method, url_path);
+                    if let Some(existing) = routes.insert(name.clone(), route.clone()) {
+                        return Err(failure::format_err!(
+                            "Request name {} is used by both {} and {}",
+                            name,
+                            existing,
+                            route
+                        ));
+                    }
 
-                    for ref_or_parameter in operation.parameters {
-                        if let ReferenceOr::Item(parameter) = ref_or_parameter {
-                            match parameter {
-                                Parameter::Query {
-                                    parameter_data,
-                                    allow_reserved: _,
-                                    style: _,
-                                    allow_empty_value: _,
-                                } => {
-                                    vars.push(Box::new(Project::parse_parameter_data(
-                                        parameter_data,
-                                    )));
-                                }
-                                Parameter::Path {
-                                    parameter_data,
-                                    style: _,
-                                } => {
-                                    vars.push(Box::new(Project::parse_parameter_data(
-                                        parameter_data,
-                                    )));
-                                }
-
-                                _ => {} //Need to finish
-                            }
+                    let mut parameters = vec![];
+                    for ref_or_parameter in operation.parameters.iter() {
+                        parameters.push(resolve_parameter(ref_or_parameter, &components, &route)?);
+                    }
+                    // path-level parameters apply to every operation,
+                    // unless it has its own of the same name and location
+                    let mut inherited = vec![];
+                    for ref_or_parameter in path_item.parameters.iter() {
+                        let (parameter_data, location) =
+                            resolve_parameter(ref_or_parameter, &components, &route)?;
+                        if !parameters.iter().any(|(own, own_location)| {
+                            own.name == parameter_data.name && *own_location == location
+                        }) {
+                            inherited.push((parameter_data, location));
                         }
                     }
 
-                    let error_type = operation
-                        .responses
-                        .default
-                        .map(Project::parse_response)
-                        .unwrap_or_else(|| "ResponseEmpty".to_string());
-
-                    let mut response_type = operation
-                        .responses
-                        .responses
-                        .values()
-                        .next()
-                        .map(|response| Project::parse_response(response.clone()))
-                        .map(|response| {
-                            if response.chars().count() == 0 {
-                                "ResponceEmpty".to_string()
-                            } else {
-                                response
-                            }
-                        })
-                        .unwrap_or_else(|| "ResponceEmpty".to_string());
-                    if arr_types.contains_key(&response_type) {
-                        response_type = format!("[{}]", arr_types[&response_type].clone());
-                        // println!("{}",arr_types[&response_type].clone());
+                    let mut vars: Vec<Box<Variable>> = vec![];
+                    for (parameter_data, location) in inherited.into_iter().chain(parameters) {
+                        vars.push(Box::new(Project::parse_parameter_data(
+                            &name,
+                            parameter_data,
+                            location,
+                            &components,
+                            &mut project.models,
+                        )?));
+                    }
+
+                    let body = match operation.request_body {
+                        Some(request_body) => Project::parse_request_body(
+                            &name,
+                            request_body,
+                            &components,
+                            &mut project.models,
+                        )?,
+                        None => None,
+                    };
+
+                    let mut responses = vec![];
+                    for (status, response) in operation.responses.responses {
+                        responses.push(Project::parse_response(
+                            &name,
+                            status.to_string(),
+                            response,
+                            &components,
+                            &mut project.models,
+                        )?);
+                    }
+                    if let Some(response) = operation.responses.default {
+                        responses.push(Project::parse_response(
+                            &name,
+                            "default".to_string(),
+                            response,
+                            &components,
+                            &mut project.models,
+                        )?);
                     }
 
-                    let name = format!("{}{}request", &url_path, &method)
-                        .replace("/", "")
-                        .replace("{", "")
-                        .replace("}", "");
+                    // top-level requirements were copied into operations by `convert::inherit_security`
+                    let security = security::parse_requirements(
+                        &name,
+                        operation.security,
+                        &project.security_schemes,
+                    )?;
 
                     let request = Request {
                         name,
                         vars,
-                        path: split_url_path(&url_path),
+                        path: url_path.clone(),
                         method,
-                        response_type,
-                        error_type,
+                        response_type: success_type(&responses),
+                        responses,
+                        body,
+                        summary: operation.summary,
+                        description: operation.description,
+                        deprecated: operation.deprecated,
+                        security,
+                        tags: operation.tags,
                     };
                     project.requests.push(request);
                 }
@@ -300,6 +1051,35 @@ impl Project {
     }
 }
 
+/// a parameter's data and location, following a reference into `components.parameters`
+fn resolve_parameter(
+    ref_or_parameter: &ReferenceOr<Parameter>,
+    components: &Components,
+    route: &str,
+) -> CliResult<(ParameterData, Location)> {
+    let parameter = match ref_or_parameter {
+        ReferenceOr::Item(parameter) => parameter.clone(),
+        ReferenceOr::Reference { reference } => {
+            match components.parameters.get(&reference_name(reference)) {
+                Some(ReferenceOr::Item(parameter)) => parameter.clone(),
+                _ => {
+                    return Err(failure::format_err!(
+                        "Unresolved reference on {}: {} does not exist in components.parameters",
+                        route,
+                        reference
+                    ));
+                }
+            }
+        }
+    };
+    Ok(match parameter {
+        Parameter::Path { parameter_data, .. } => (parameter_data, Location::Path),
+        Parameter::Query { parameter_data, .. } => (parameter_data, Location::Query),
+        Parameter::Header { parameter_data, .. } => (parameter_data, Location::Header),
+        Parameter::Cookie { parameter_data, .. } => (parameter_data, Location::Cookie),
+    })
+}
+
 trait Additional {
     fn path_to_request(&self) -> Vec<(Operation, Method)>;
 }
@@ -339,8 +1119,875 @@ impl CustomIterators for Vec<Request> {
     }
 }
 
-fn split_url_path(url: &str) -> String {
-    let mut url = url.split("/").collect::<Vec<_>>();
-    url.remove(url.len() - 1);
-    url.join("/")
+/// simple form of the lowest 2xx response type, eg. `Pet` or `[Pet]`
+fn success_type(responses: &[StatusResponse]) -> String {
+    responses
+        .iter()
+        .filter(|response| response.status.starts_with('2'))
+        // `200` < `201` < `2XX`
+        .min_by_key(|response| response.status.clone())
+        .and_then(|response| match &response.response_type {
+            Some(VariableType::ComplexType(name)) => Some(name.clone()),
+            Some(VariableType::ArrayType(item_type)) => match item_type.as_ref() {
+                VariableType::ComplexType(name) => Some(format!("[{}]", name)),
+                _ => None,
+            },
+            _ => None,
+        })
+        .unwrap_or_else(|| "ResponceEmpty".to_string())
+}
+
+/// alphanumeric runs of a string, eg. `user-accounts` -> `user`, `accounts`
+fn words(text: &str) -> impl Iterator<Item = &str> {
+    text.split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+}
+
+/// eg. `owner_address` -> `OwnerAddress`
+pub(crate) fn pascal_case(text: &str) -> String {
+    words(text).map(capitalise).collect()
+}
+
+fn capitalise(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// camelCased `operationId` when present, otherwise built from the route,
+/// eg. `GET /pets/{petId}` -> `getPetsByPetId`
+fn request_name(operation_id: Option<&str>, method: &Method, url_path: &str) -> String {
+    let name: String = words(operation_id.unwrap_or(""))
+        .enumerate()
+        .map(|(i, word)| {
+            if i == 0 {
+                word.to_string()
+            } else {
+                capitalise(word)
+            }
+        })
+        .collect();
+    if !name.is_empty() {
+        return name;
+    }
+
+    let mut name = method.to_string().to_lowercase();
+    for segment in url_path.split('/') {
+        if segment.starts_with('{') {
+            name.push_str("By");
+        }
+        for word in words(segment) {
+            name.push_str(&capitalise(word));
+        }
+    }
+    name
+}
+
+#[test]
+fn test_reference_properties() {
+    let spec = r#"
+openapi: 3.0.0
+info: { title: test, version: 0.0.1 }
+paths: {}
+components:
+  schemas:
+    Customer:
+      type: object
+      properties:
+        name: { type: string }
+    Customers:
+      type: array
+      items: { $ref: '#/components/schemas/Customer' }
+    Order:
+      type: object
+      required: [customer]
+      properties:
+        customer: { $ref: '#/components/schemas/Customer' }
+        previous: { type: array, items: { $ref: '#/components/schemas/Customer' } }
+        others: { $ref: '#/components/schemas/Customers' }
+"#;
+    let project = Project::parse_yml(serde_yaml::from_str(spec).unwrap()).unwrap();
+    let order = project
+        .models
+        .iter()
+        .find(|model| model.name == "Order")
+        .unwrap();
+    let customer = VariableType::ComplexType("Customer".to_string());
+    let customers = VariableType::ArrayType(Box::new(customer.clone()));
+    assert_eq!(order.vars[0].variable_type, customer);
+    assert!(!order.vars[0].optional);
+    assert_eq!(order.vars[1].variable_type, customers);
+    assert_eq!(order.vars[2].variable_type, customers);
+
+    let dangling = spec.replace(
+        "items: { $ref: '#/components/schemas/Customer' } }",
+        "items: { $ref: '#/components/schemas/Missing' } }",
+    );
+    let err = Project::parse_yml(serde_yaml::from_str(&dangling).unwrap()).unwrap_err();
+    assert!(format!("{}", err).contains("#/components/schemas/Missing"));
+}
+
+#[test]
+fn test_all_of_inheritance() {
+    let spec = r#"
+openapi: 3.0.0
+info: { title: test, version: 0.0.1 }
+paths: {}
+components:
+  schemas:
+    Pet:
+      type: object
+      required: [name]
+      properties:
+        name: { type: string }
+        tag: { type: string }
+    Dog:
+      allOf:
+        - $ref: '#/components/schemas/Pet'
+        - required: [tag]
+          properties:
+            bark: { type: boolean }
+"#;
+    let project = Project::parse_yml(serde_yaml::from_str(spec).unwrap()).unwrap();
+    let dog = project
+        .models
+        .iter()
+        .find(|model| model.name == "Dog")
+        .unwrap();
+    assert_eq!(dog.extends, Some("Pet".to_string()));
+    let vars: Vec<(&str, bool)> = dog
+        .vars
+        .iter()
+        .map(|var| (var.name.as_str(), var.optional))
+        .collect();
+    assert_eq!(vars, vec![("name", false), ("tag", false), ("bark", true)]);
+}
+
+#[test]
+fn test_one_of_union() {
+    let spec = r#"
+openapi: 3.0.0
+info: { title: test, version: 0.0.1 }
+paths: {}
+components:
+  schemas:
+    Cat:
+      type: object
+      properties:
+        petType: { type: string }
+    Dog:
+      type: object
+      properties:
+        petType: { type: string }
+    Pet:
+      oneOf:
+        - $ref: '#/components/schemas/Cat'
+        - $ref: '#/components/schemas/Dog'
+      discriminator:
+        propertyName: petType
+        mapping:
+          doggo: '#/components/schemas/Dog'
+"#;
+    let project = Project::parse_yml(serde_yaml::from_str(spec).unwrap()).unwrap();
+    let pet = project
+        .models
+        .iter()
+        .find(|model| model.name == "Pet")
+        .unwrap();
+    let union = pet.union.clone().unwrap();
+    assert_eq!(union.kind, UnionKind::OneOf);
+    assert_eq!(union.variants, vec!["Cat".to_string(), "Dog".to_string()]);
+    let discriminator = union.discriminator.unwrap();
+    assert_eq!(discriminator.property_name, "petType");
+    let mapping: Vec<(&str, &str)> = discriminator
+        .mapping
+        .iter()
+        .map(|(k, v)| (k.as_str(), v.as_str()))
+        .collect();
+    assert_eq!(mapping, vec![("Cat", "Cat"), ("doggo", "Dog")]);
+    assert!(pet.vars.is_empty());
+
+    // unions on properties become models of their own, inline variants too
+    let spec = r#"
+openapi: 3.0.0
+info: { title: test, version: 0.0.1 }
+paths: {}
+components:
+  schemas:
+    Cat: { type: object, properties: { lives: { type: integer } } }
+    Owner:
+      type: object
+      properties:
+        pet:
+          oneOf:
+            - $ref: '#/components/schemas/Cat'
+            - { type: object, properties: { barks: { type: boolean } } }
+        cat: { anyOf: [{ $ref: '#/components/schemas/Cat' }], nullable: true }
+"#;
+    let project = Project::parse_yml(serde_yaml::from_str(spec).unwrap()).unwrap();
+    let owner = project
+        .models
+        .iter()
+        .find(|model| model.name == "Owner")
+        .unwrap();
+    assert_eq!(
+        owner.vars[0].variable_type,
+        VariableType::ComplexType("OwnerPet".to_string())
+    );
+    assert_eq!(
+        owner.vars[1].variable_type,
+        VariableType::ComplexType("Cat".to_string())
+    );
+    let pet = project
+        .models
+        .iter()
+        .find(|model| model.name == "OwnerPet")
+        .unwrap();
+    assert_eq!(
+        pet.union.clone().unwrap().variants,
+        vec!["Cat".to_string(), "OwnerPetVariant2".to_string()]
+    );
+    let variant = project
+        .models
+        .iter()
+        .find(|model| model.name == "OwnerPetVariant2")
+        .unwrap();
+    assert_eq!(variant.vars[0].name, "barks");
+}
+
+#[test]
+fn test_formats() {
+    let spec = r#"
+openapi: 3.0.0
+info: { title: test, version: 0.0.1 }
+paths: {}
+components:
+  schemas:
+    Upload:
+      type: object
+      properties:
+        id: { type: string, format: uuid }
+        size: { type: integer, format: int64 }
+        ratio: { type: number, format: double }
+        created: { type: string, format: date-time }
+        content: { type: string, format: binary }
+        secret: { type: string, format: password }
+"#;
+    let project = Project::parse_yml(serde_yaml::from_str(spec).unwrap()).unwrap();
+    let types: Vec<VariableType> = project.models[0]
+        .vars
+        .iter()
+        .map(|var| var.variable_type.clone())
+        .collect();
+    assert_eq!(
+        &types[..6],
+        &[
+            VariableType::UuidType,
+            VariableType::Int64Type,
+            VariableType::DoubleType,
+            VariableType::DateTimeType,
+            VariableType::BinaryType,
+            VariableType::StringType,
+        ]
+    );
+    assert_eq!(VariableType::Int64Type.to_mysql(), "BIGINT");
+}
+
+#[test]
+fn test_request_bodies() {
+    let spec = r#"
+openapi: 3.0.0
+info: { title: test, version: 0.0.1 }
+paths:
+  /pets:
+    post:
+      requestBody:
+        required: true
+        content:
+          application/xml: { schema: { $ref: '#/components/schemas/Pet' } }
+          application/json:
+            schema:
+              type: object
+              properties:
+                name: { type: string }
+      responses: {}
+    put:
+      requestBody: { $ref: '#/components/requestBodies/PetBody' }
+      responses: {}
+components:
+  schemas:
+    Pet:
+      type: object
+      properties:
+        name: { type: string }
+  requestBodies:
+    PetBody:
+      content:
+        application/xml: { schema: { $ref: '#/components/schemas/Pet' } }
+"#;
+    let project = Project::parse_yml(serde_yaml::from_str(spec).unwrap()).unwrap();
+    let post = project
+        .requests
+        .iter()
+        .find(|request| request.method == Method::Post_)
+        .unwrap();
+    let body = post.body.clone().unwrap();
+    assert_eq!(body.media_type, "application/json");
+    assert!(body.required);
+    assert_eq!(
+        body.body_type,
+        VariableType::ComplexType("PostPetsBody".to_string())
+    );
+    assert_eq!(body.model.unwrap().vars[0].name, "name");
+
+    let put = project
+        .requests
+        .iter()
+        .find(|request| request.method == Method::Put_)
+        .unwrap();
+    let body = put.body.clone().unwrap();
+    assert_eq!(body.media_type, "application/xml");
+    assert!(!body.required);
+    assert_eq!(body.body_type, VariableType::ComplexType("Pet".to_string()));
+    assert!(body.model.is_none());
+
+    // an inline body doesn't take the name of a component
+    let spec = spec.replace(
+        "    Pet:\n",
+        "    PostPetsBody: { type: object }\n    Pet:\n",
+    );
+    let project = Project::parse_yml(serde_yaml::from_str(&spec).unwrap()).unwrap();
+    let post = project
+        .requests
+        .iter()
+        .find(|request| request.method == Method::Post_)
+        .unwrap();
+    assert_eq!(
+        post.body.clone().unwrap().body_type,
+        VariableType::ComplexType("PostPetsBody2".to_string())
+    );
+}
+
+#[test]
+fn test_parameter_locations() {
+    let spec = r#"
+openapi: 3.0.0
+info: { title: test, version: 0.0.1 }
+paths:
+  /pets/{petId}:
+    parameters:
+      - { name: petId, in: path, required: true, schema: { type: integer } }
+      - { name: fields, in: query, schema: { type: integer } }
+    get:
+      parameters:
+        - { name: fields, in: query, schema: { type: string } }
+        - { $ref: '#/components/parameters/RequestId' }
+        - { name: session, in: cookie, schema: { type: string } }
+      responses: {}
+    delete:
+      responses: {}
+components:
+  parameters:
+    RequestId: { name: X-Request-Id, in: header, schema: { type: string, format: uuid } }
+"#;
+    let project = Project::parse_yml(serde_yaml::from_str(spec).unwrap()).unwrap();
+    let vars: Vec<(&str, Option<Location>)> = project.requests[0]
+        .vars
+        .iter()
+        .map(|var| (var.name.as_str(), var.location.clone()))
+        .collect();
+    assert_eq!(
+        vars,
+        vec![
+            ("petId", Some(Location::Path)),
+            ("fields", Some(Location::Query)),
+            ("X-Request-Id", Some(Location::Header)),
+            ("session", Some(Location::Cookie)),
+        ]
+    );
+    // path-level parameters are shared, but an operation's own take their place
+    assert_eq!(
+        project.requests[0].vars[1].variable_type,
+        VariableType::StringType
+    );
+    let delete = &project.requests[1];
+    assert_eq!(
+        delete
+            .vars
+            .iter()
+            .map(|var| (var.name.as_str(), var.variable_type.clone()))
+            .collect::<Vec<_>>(),
+        vec![
+            ("petId", VariableType::IntType),
+            ("fields", VariableType::IntType),
+        ]
+    );
+}
+
+#[test]
+fn test_responses() {
+    let spec = r#"
+openapi: 3.0.0
+info: { title: test, version: 0.0.1 }
+paths:
+  /pets:
+    post:
+      responses:
+        '404': { $ref: '#/components/responses/NotFound' }
+        '201':
+          description: created
+          headers:
+            Location: { schema: { type: string } }
+          content:
+            application/json: { schema: { $ref: '#/components/schemas/Pets' } }
+        '200':
+          description: ok
+        default:
+          description: error
+          content:
+            application/json: { schema: { $ref: '#/components/schemas/Error' } }
+components:
+  schemas:
+    Pet:
+      type: object
+      properties:
+        name: { type: string }
+    Pets:
+      type: array
+      items: { $ref: '#/components/schemas/Pet' }
+    Error:
+      type: object
+      properties:
+        message: { type: string }
+  responses:
+    NotFound: { description: not found }
+"#;
+    let project = Project::parse_yml(serde_yaml::from_str(spec).unwrap()).unwrap();
+    let request = &project.requests[0];
+    let statuses: Vec<&str> = request
+        .responses
+        .iter()
+        .map(|response| response.status.as_str())
+        .collect();
+    assert_eq!(statuses, vec!["404", "201", "200", "default"]);
+    assert_eq!(request.responses[1].headers[0].name, "Location");
+    assert_eq!(
+        request.responses[1].media_type,
+        Some("application/json".to_string())
+    );
+    assert_eq!(request.responses[2].response_type, None);
+    assert_eq!(
+        request.responses[3].response_type,
+        Some(VariableType::ComplexType("Error".to_string()))
+    );
+    // 200 has no content, so the success type stays empty rather than falling through to 201
+    assert_eq!(request.response_type, "ResponceEmpty");
+    assert_eq!(success_type(&request.responses[..2]), "[Pet]");
+}
+
+#[test]
+fn test_request_names() {
+    assert_eq!(
+        request_name(Some("list_pets"), &Method::Get_, "/pets"),
+        "listPets"
+    );
+    assert_eq!(
+        request_name(None, &Method::Get_, "/pets/{petId}"),
+        "getPetsByPetId"
+    );
+    assert_ne!(
+        request_name(None, &Method::Get_, "/a/bc"),
+        request_name(None, &Method::Get_, "/ab/c")
+    );
+
+    let spec = r#"
+openapi: 3.0.0
+info: { title: test, version: 0.0.1 }
+paths:
+  /pets:
+    get: { operationId: listPets, responses: {} }
+  /animals:
+    get: { operationId: listPets, responses: {} }
+"#;
+    let err = Project::parse_yml(serde_yaml::from_str(spec).unwrap()).unwrap_err();
+    assert_eq!(
+        format!("{}", err),
+        "Request name listPets is used by both GET /pets and GET /animals"
+    );
+}
+
+#[test]
+fn test_inline_objects() {
+    let spec = r#"
+openapi: 3.0.0
+info: { title: test, version: 0.0.1 }
+paths:
+  /pets:
+    get:
+      operationId: listPets
+      responses:
+        '200':
+          description: ok
+          content:
+            application/json:
+              schema: { type: array, items: { type: object, properties: { name: { type: string } } } }
+components:
+  schemas:
+    Pet:
+      type: object
+      properties:
+        owner:
+          type: object
+          properties:
+            address: { type: object, properties: { city: { type: string } } }
+        ownerAddress: { type: object, properties: { street: { type: string } } }
+        tags: { type: array, items: { type: object, properties: { label: { type: string } } } }
+    PetTagsItem:
+      type: object
+      properties:
+        label: { type: string }
+"#;
+    let project = Project::parse_yml(serde_yaml::from_str(spec).unwrap()).unwrap();
+    let names: Vec<&str> = project
+        .models
+        .iter()
+        .map(|model| model.name.as_str())
+        .collect();
+    assert_eq!(
+        names,
+        vec![
+            "PetOwnerAddress",
+            "PetOwner",
+            "PetOwnerAddress2",
+            "PetTagsItem2",
+            "Pet",
+            "PetTagsItem",
+            "ListPets200ResponseItem"
+        ]
+    );
+    let pet = project
+        .models
+        .iter()
+        .find(|model| model.name == "Pet")
+        .unwrap();
+    assert_eq!(
+        pet.vars[0].variable_type,
+        VariableType::ComplexType("PetOwner".to_string())
+    );
+    assert_eq!(
+        pet.vars[2].variable_type,
+        VariableType::ArrayType(Box::new(VariableType::ComplexType(
+            "PetTagsItem2".to_string()
+        )))
+    );
+    assert_eq!(
+        project.requests[0].response_type,
+        "[ListPets200ResponseItem]"
+    );
+}
+
+#[test]
+fn test_maps() {
+    let spec = r#"
+openapi: 3.0.0
+info: { title: test, version: 0.0.1 }
+paths: {}
+components:
+  schemas:
+    Pet:
+      type: object
+      properties:
+        labels: { type: object, additionalProperties: { type: integer } }
+        metadata: { type: object }
+        owners: { type: object, additionalProperties: { type: object, properties: { name: { type: string } } } }
+"#;
+    let project = Project::parse_yml(serde_yaml::from_str(spec).unwrap()).unwrap();
+    let pet = project
+        .models
+        .iter()
+        .find(|model| model.name == "Pet")
+        .unwrap();
+    let map = |value: VariableType| {
+        VariableType::MapType(Box::new(VariableType::StringType), Box::new(value))
+    };
+    assert_eq!(pet.vars[0].variable_type, map(VariableType::IntType));
+    assert_eq!(pet.vars[1].variable_type, map(VariableType::AnyType));
+    assert_eq!(
+        pet.vars[2].variable_type,
+        map(VariableType::ComplexType("PetOwnersValue".to_string()))
+    );
+    assert_eq!(pet.vars[0].variable_type.to_mysql(), "JSON");
+
+    // map components are resolved where they are referenced rather than being models
+    let spec = r#"
+openapi: 3.0.0
+info: { title: test, version: 0.0.1 }
+paths: {}
+components:
+  schemas:
+    Labels: { type: object, additionalProperties: { type: integer } }
+    Owners: { type: object, additionalProperties: { type: object, properties: { name: { type: string } } } }
+    Pet:
+      type: object
+      properties:
+        labels: { $ref: '#/components/schemas/Labels' }
+        owners: { $ref: '#/components/schemas/Owners' }
+"#;
+    let project = Project::parse_yml(serde_yaml::from_str(spec).unwrap()).unwrap();
+    let mut names = project
+        .models
+        .iter()
+        .map(|model| model.name.as_str())
+        .collect::<Vec<_>>();
+    names.sort();
+    assert_eq!(names, vec!["OwnersValue", "Pet"]);
+    let pet = project
+        .models
+        .iter()
+        .find(|model| model.name == "Pet")
+        .unwrap();
+    assert_eq!(pet.vars[0].variable_type, map(VariableType::IntType));
+    assert_eq!(
+        pet.vars[1].variable_type,
+        map(VariableType::ComplexType("OwnersValue".to_string()))
+    );
+}
+
+#[test]
+fn test_documentation() {
+    let spec = r#"
+openapi: 3.0.0
+info: { title: test, version: 0.0.1 }
+paths:
+  /pets:
+    get:
+      summary: List pets
+      deprecated: true
+      parameters:
+        - { name: limit, in: query, description: page size, schema: { type: integer, default: 20, example: 5 } }
+      responses:
+        '200': { description: a page of pets }
+components:
+  schemas:
+    Pet:
+      type: object
+      description: a pet
+      example: { name: rex }
+      properties:
+        name: { type: string, description: the pet's name, default: rex, deprecated: true }
+"#;
+    let project = Project::parse_yml(serde_yaml::from_str(spec).unwrap()).unwrap();
+    let pet = project
+        .models
+        .iter()
+        .find(|model| model.name == "Pet")
+        .unwrap();
+    assert_eq!(pet.description.as_deref(), Some("a pet"));
+    assert_eq!(pet.example.as_deref(), Some(r#"{"name":"rex"}"#));
+    assert_eq!(pet.vars[0].description.as_deref(), Some("the pet's name"));
+    assert_eq!(pet.vars[0].value.as_deref(), Some("rex"));
+    assert!(pet.vars[0].deprecated);
+
+    let request = &project.requests[0];
+    assert_eq!(request.summary.as_deref(), Some("List pets"));
+    assert!(request.deprecated);
+    assert_eq!(request.vars[0].description.as_deref(), Some("page size"));
+    assert_eq!(request.vars[0].value.as_deref(), Some("20"));
+    assert_eq!(request.vars[0].example.as_deref(), Some("5"));
+    assert_eq!(
+        request.responses[0].description.as_deref(),
+        Some("a page of pets")
+    );
+}
+
+#[test]
+fn test_constraints() {
+    let spec = r#"
+openapi: 3.0.0
+info: { title: test, version: 0.0.1 }
+paths: {}
+components:
+  schemas:
+    Pet:
+      type: object
+      properties:
+        age: { type: integer, minimum: 0, exclusiveMinimum: true, multipleOf: 2 }
+        name: { type: string, maxLength: 32, pattern: '^[a-z]+$' }
+        tags: { type: array, items: { type: string }, minItems: 1, uniqueItems: true }
+"#;
+    let project = Project::parse_yml(serde_yaml::from_str(spec).unwrap()).unwrap();
+    let pet = project
+        .models
+        .iter()
+        .find(|model| model.name == "Pet")
+        .unwrap();
+    assert_eq!(
+        pet.vars[0].constraints,
+        Constraints {
+            minimum: Some(0.0),
+            exclusive_minimum: true,
+            multiple_of: Some(2.0),
+            ..Default::default()
+        }
+    );
+    assert_eq!(pet.vars[1].constraints.max_length, Some(32));
+    assert_eq!(pet.vars[1].constraints.pattern.as_deref(), Some("^[a-z]+$"));
+    assert_eq!(
+        pet.vars[2].constraints,
+        Constraints {
+            min_items: Some(1),
+            unique_items: true,
+            ..Default::default()
+        }
+    );
+}
+
+#[test]
+fn test_access_flags() {
+    let spec = r#"
+openapi: 3.0.0
+info: { title: test, version: 0.0.1 }
+paths: {}
+components:
+  schemas:
+    User:
+      type: object
+      required: [id, password]
+      properties:
+        id: { type: integer, readOnly: true }
+        password: { type: string, writeOnly: true }
+        nickname: { type: string, nullable: true }
+"#;
+    let project = Project::parse_yml(serde_yaml::from_str(spec).unwrap()).unwrap();
+    let user = project
+        .models
+        .iter()
+        .find(|model| model.name == "User")
+        .unwrap();
+    assert!(user.vars[0].read_only && !user.vars[0].write_only);
+    assert!(user.vars[1].write_only && !user.vars[1].optional);
+    assert!(user.vars[2].nullable && user.vars[2].optional);
+}
+
+#[test]
+fn test_servers() {
+    let spec = r#"
+openapi: 3.0.0
+info: { title: test, version: 0.0.1 }
+servers:
+  - url: 'https://{region}.example.com/{version}'
+    description: production
+    variables:
+      region: { default: eu, enum: [eu, us] }
+      version: { default: v1, description: api version }
+  - url: 'http://localhost:8080/v1'
+paths: {}
+"#;
+    let project = Project::parse_yml(serde_yaml::from_str(spec).unwrap()).unwrap();
+    assert_eq!(project.info.host, "https://eu.example.com");
+    assert_eq!(project.info.endpoint, "/v1");
+    assert_eq!(project.info.servers.len(), 2);
+    let region = &project.info.servers[0].variables["region"];
+    assert_eq!(region.enumeration, vec!["eu", "us"]);
+    assert_eq!(
+        project.info.servers[0].variables["version"]
+            .description
+            .as_deref(),
+        Some("api version")
+    );
+
+    let spec = spec.replace(
+        "      version: { default: v1, description: api version }\n",
+        "",
+    );
+    let err = Project::parse_yml(serde_yaml::from_str(&spec).unwrap()).unwrap_err();
+    assert_eq!(format!("{}", err), "Server https://{region}.example.com/{version} uses {version} which is not one of its variables");
+}
+
+#[test]
+fn test_security() {
+    let spec = r#"
+openapi: 3.0.0
+info: { title: test, version: 0.0.1 }
+security: [{ petstore_auth: [read:pets] }]
+paths:
+  /pets:
+    get:
+      responses: { '200': { description: ok } }
+    post:
+      security: [{ api_key: [] }, { basic: [] }]
+      responses: { '201': { description: created } }
+  /health:
+    get:
+      security: []
+      responses: { '200': { description: ok } }
+components:
+  securitySchemes:
+    api_key: { type: apiKey, in: header, name: X-API-Key }
+    basic: { type: http, scheme: basic }
+    petstore_auth:
+      type: oauth2
+      flows:
+        implicit: { authorizationUrl: 'https://example.com/authorize', scopes: { read:pets: read your pets } }
+        clientCredentials: { tokenUrl: 'https://example.com/token', scopes: {} }
+"#;
+    let mut spec: serde_json::Value = serde_yaml::from_str(spec).unwrap();
+    crate::convert::inherit_security(&mut spec);
+    let project = Project::parse_yml(serde_json::from_value(spec).unwrap()).unwrap();
+
+    let scheme = |name: &str| {
+        project
+            .security_schemes
+            .iter()
+            .find(|scheme| scheme.name == name)
+            .unwrap()
+            .kind
+            .clone()
+    };
+    assert_eq!(
+        scheme("api_key"),
+        security::SecurityKind::ApiKey {
+            location: Location::Header,
+            name: "X-API-Key".to_string()
+        }
+    );
+    match scheme("petstore_auth") {
+        security::SecurityKind::OAuth2 { flows } => {
+            let kinds: Vec<security::OAuthFlowKind> =
+                flows.iter().map(|flow| flow.kind.clone()).collect();
+            assert_eq!(
+                kinds,
+                vec![
+                    security::OAuthFlowKind::Implicit,
+                    security::OAuthFlowKind::ClientCredentials
+                ]
+            );
+            assert_eq!(
+                flows[1].token_url.as_deref(),
+                Some("https://example.com/token")
+            );
+            assert_eq!(flows[0].scopes["read:pets"], "read your pets");
+        }
+        kind => panic!("expected oauth2, got {:?}", kind),
+    }
+
+    let request = |name: &str| {
+        project
+            .requests
+            .iter()
+            .find(|request| request.name == name)
+            .unwrap()
+    };
+    assert_eq!(
+        request("getPets").security,
+        vec![
+            vec![("petstore_auth".to_string(), vec!["read:pets".to_string()])]
+                .into_iter()
+                .collect()
+        ]
+    );
+    assert_eq!(request("postPets").security.len(), 2);
+    assert!(request("getHealth").security.is_empty());
 }
@@ -1,8 +1,58 @@
 use super::*;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
-#[derive(Debug, PartialEq, Clone, Deserialize, Serialize)]
+#[derive(Debug, Default, PartialEq, Clone, Deserialize, Serialize)]
 pub struct Model {
     pub name: String,
     pub vars: Vec<Box<Variable>>,
+    /// parent model this one inherits from via `allOf`; its variables are already flattened into `vars`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extends: Option<String>,
+    /// set when the model is a `oneOf`/`anyOf` of other models rather than an object
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub union: Option<Union>,
+    /// allowed values when the model is a string enum rather than an object
+    #[serde(rename = "enum", default, skip_serializing_if = "Option::is_none")]
+    pub enumeration: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// the schema's `example` as JSON
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub example: Option<String>,
+    #[serde(default)]
+    pub deprecated: bool,
+    /// names of the variables making up the primary key, see `Key`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub keys: Vec<String>,
+}
+
+impl Model {
+    /// whether the model is a plain object, as opposed to a union or an enum
+    pub fn is_object(&self) -> bool {
+        self.union.is_none() && self.enumeration.is_none()
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize)]
+pub struct Union {
+    pub kind: UnionKind,
+    /// names of the models this union may hold
+    pub variants: Vec<String>,
+    pub discriminator: Option<Discriminator>,
+}
+
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize)]
+pub enum UnionKind {
+    #[serde(rename = "oneOf")]
+    OneOf,
+    #[serde(rename = "anyOf")]
+    AnyOf,
+}
+
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize)]
+pub struct Discriminator {
+    pub property_name: String,
+    /// discriminator value -> model name, including the implicit one for each unmapped variant
+    pub mapping: BTreeMap<String, String>,
 }
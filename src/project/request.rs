@@ -4,11 +4,53 @@ use serde::{Deserialize, Serialize};
 #[derive(Debug, PartialEq, Clone, Deserialize, Serialize)]
 pub struct Request {
     pub name: String,
+    /// the full path template, eg. `/pets/{petId}/photo`
     pub path: String,
     pub vars: Vec<Box<Variable>>,
     pub method: Method,
+    /// simple form of the success response, eg. `Pet` or `[Pet]`; see `responses` for the rest
     pub response_type: String,
-    pub error_type: String,
+    #[serde(default)]
+    pub responses: Vec<StatusResponse>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<Body>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub deprecated: bool,
+    /// operation tags, the first of which decides the requests file when grouping by tag
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// alternative ways to authenticate, any one of which will do; empty when none is needed
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub security: Vec<security::SecurityRequirement>,
+}
+
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize)]
+pub struct StatusResponse {
+    /// status code, range such as `4XX`, or `default`
+    pub status: String,
+    pub media_type: Option<String>,
+    /// unset when the response has no content
+    #[serde(rename = "type")]
+    pub response_type: Option<VariableType>,
+    pub headers: Vec<Variable>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+/// payload of a POST/PUT/PATCH etc. request
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize)]
+pub struct Body {
+    pub media_type: String,
+    #[serde(rename = "type")]
+    pub body_type: VariableType,
+    /// the payload's own model when the schema is an inline object rather than a `$ref`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<Model>,
+    pub required: bool,
 }
 
 #[derive(Debug, PartialEq, Clone, Deserialize, Serialize)]
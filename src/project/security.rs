@@ -0,0 +1,182 @@
+use super::*;
+use serde::{Deserialize, Serialize};
+
+/// scheme name -> scopes required of it; a request needs every scheme of any one requirement
+pub type SecurityRequirement = BTreeMap<String, Vec<String>>;
+
+/// a way of authenticating requests, from `components.securitySchemes`
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize)]
+pub struct SecurityScheme {
+    pub name: String,
+    #[serde(flatten)]
+    pub kind: SecurityKind,
+}
+
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize)]
+#[serde(tag = "type")]
+pub enum SecurityKind {
+    /// a key sent as the `name` header, query parameter or cookie
+    #[serde(rename = "apiKey")]
+    ApiKey { location: Location, name: String },
+    /// an `Authorization` header, eg. `bearer` or `basic`
+    #[serde(rename = "http")]
+    Http {
+        scheme: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        bearer_format: Option<String>,
+    },
+    #[serde(rename = "oauth2")]
+    OAuth2 { flows: Vec<OAuthFlow> },
+    #[serde(rename = "openIdConnect")]
+    OpenIdConnect { url: String },
+}
+
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize)]
+pub struct OAuthFlow {
+    pub kind: OAuthFlowKind,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub authorization_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub refresh_url: Option<String>,
+    /// scope -> description
+    pub scopes: BTreeMap<String, String>,
+}
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Deserialize, Serialize)]
+pub enum OAuthFlowKind {
+    #[serde(rename = "implicit")]
+    Implicit,
+    #[serde(rename = "password")]
+    Password,
+    #[serde(rename = "clientCredentials")]
+    ClientCredentials,
+    #[serde(rename = "authorizationCode")]
+    AuthorizationCode,
+}
+
+pub(crate) fn parse_security_scheme(
+    name: String,
+    scheme: openapiv3::SecurityScheme,
+) -> SecurityScheme {
+    let kind = match scheme {
+        openapiv3::SecurityScheme::APIKey { location, name } => SecurityKind::ApiKey {
+            location: match location {
+                APIKeyLocation::Query => Location::Query,
+                APIKeyLocation::Header => Location::Header,
+                APIKeyLocation::Cookie => Location::Cookie,
+            },
+            name,
+        },
+        openapiv3::SecurityScheme::HTTP {
+            scheme,
+            bearer_format,
+        } => SecurityKind::Http {
+            scheme,
+            bearer_format,
+        },
+        openapiv3::SecurityScheme::OAuth2 { flows } => {
+            // `openapiv3` doesn't keep each flow in its own field, so go by the variant
+            let mut flows: Vec<OAuthFlow> = vec![
+                flows.implicit,
+                flows.password,
+                flows.client_credentials,
+                flows.authorization_code,
+            ]
+            .into_iter()
+            .flatten()
+            .map(parse_flow)
+            .collect();
+            flows.sort_by(|a, b| a.kind.cmp(&b.kind));
+            SecurityKind::OAuth2 { flows }
+        }
+        openapiv3::SecurityScheme::OpenIDConnect {
+            open_id_connect_url,
+        } => SecurityKind::OpenIdConnect {
+            url: open_id_connect_url,
+        },
+    };
+    SecurityScheme { name, kind }
+}
+
+fn parse_flow(flow: OAuth2Flow) -> OAuthFlow {
+    let (kind, authorization_url, token_url, refresh_url, scopes) = match flow {
+        OAuth2Flow::Implicit {
+            authorization_url,
+            refresh_url,
+            scopes,
+        } => (
+            OAuthFlowKind::Implicit,
+            Some(authorization_url),
+            None,
+            refresh_url,
+            scopes,
+        ),
+        OAuth2Flow::Password {
+            refresh_url,
+            token_url,
+            scopes,
+        } => (
+            OAuthFlowKind::Password,
+            None,
+            Some(token_url),
+            refresh_url,
+            scopes,
+        ),
+        OAuth2Flow::ClientCredentials {
+            refresh_url,
+            token_url,
+            scopes,
+        } => (
+            OAuthFlowKind::ClientCredentials,
+            None,
+            Some(token_url),
+            refresh_url,
+            scopes,
+        ),
+        OAuth2Flow::AuthorizationCode {
+            authorization_url,
+            token_url,
+            refresh_url,
+            scopes,
+        } => (
+            OAuthFlowKind::AuthorizationCode,
+            Some(authorization_url),
+            Some(token_url),
+            refresh_url,
+            scopes,
+        ),
+    };
+    OAuthFlow {
+        kind,
+        authorization_url,
+        token_url,
+        refresh_url,
+        scopes: scopes.into_iter().collect(),
+    }
+}
+
+/// requirements as the IR holds them, checking each names a known scheme
+pub(crate) fn parse_requirements(
+    request_name: &str,
+    requirements: Vec<openapiv3::SecurityRequirement>,
+    schemes: &[SecurityScheme],
+) -> CliResult<Vec<SecurityRequirement>> {
+    let mut parsed = vec![];
+    for requirement in requirements {
+        let requirement: SecurityRequirement = requirement.into_iter().collect();
+        if let Some(name) = requirement
+            .keys()
+            .find(|name| !schemes.iter().any(|scheme| scheme.name == **name))
+        {
+            return Err(failure::format_err!(
+                "Unresolved security requirement on {}: {} does not exist in components.securitySchemes",
+                request_name,
+                name
+            ));
+        }
+        parsed.push(requirement);
+    }
+    Ok(parsed)
+}
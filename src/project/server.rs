@@ -0,0 +1,34 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// a base URL the API is served from, eg. `https://{region}.example.com/v1`
+#[derive(Debug, Default, PartialEq, Clone, Deserialize, Serialize)]
+pub struct Server {
+    pub url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// substitutions for the `{name}`s in `url`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub variables: BTreeMap<String, ServerVariable>,
+}
+
+#[derive(Debug, Default, PartialEq, Clone, Deserialize, Serialize)]
+pub struct ServerVariable {
+    pub default: String,
+    /// allowed values, any value when empty
+    #[serde(rename = "enum", default, skip_serializing_if = "Vec::is_empty")]
+    pub enumeration: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+impl Server {
+    /// `url` with every variable set to its default
+    pub fn default_url(&self) -> String {
+        self.variables
+            .iter()
+            .fold(self.url.clone(), |url, (name, variable)| {
+                url.replace(&format!("{{{}}}", name), &variable.default)
+            })
+    }
+}
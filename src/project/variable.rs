@@ -1,15 +1,89 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, PartialEq, Clone, Deserialize, Serialize)]
+#[derive(Debug, Default, PartialEq, Clone, Deserialize, Serialize)]
 pub struct Variable {
     pub name: String,
     #[serde(rename = "type")]
     pub variable_type: VariableType,
+    /// may be left out; see `nullable` for whether it may be null
     pub optional: bool,
+    #[serde(default)]
+    pub nullable: bool,
+    /// only sent in responses, eg. a server generated id
+    #[serde(default)]
+    pub read_only: bool,
+    /// only sent in requests, eg. a password
+    #[serde(default)]
+    pub write_only: bool,
+    /// the schema's `default`; strings as they are, anything else as JSON
     pub value: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// the schema's `example`, in the same form as `value`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub example: Option<String>,
+    #[serde(default)]
+    pub deprecated: bool,
+    #[serde(default, skip_serializing_if = "Constraints::is_empty")]
+    pub constraints: Constraints,
+    /// where a request variable is sent; unset for model variables
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub location: Option<Location>,
+}
+
+/// validation keywords from the schema; numbers apply to `Int`/`Float` etc., lengths to
+/// strings and items to arrays
+#[derive(Debug, Default, PartialEq, Clone, Deserialize, Serialize)]
+pub struct Constraints {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub minimum: Option<f64>,
+    /// whether `minimum` itself is excluded
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub exclusive_minimum: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub maximum: Option<f64>,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub exclusive_maximum: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub multiple_of: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_length: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_length: Option<usize>,
+    /// ECMA 262 regular expression the value must match
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_items: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_items: Option<usize>,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub unique_items: bool,
+}
+
+impl Constraints {
+    pub fn is_empty(&self) -> bool {
+        self == &Constraints::default()
+    }
+}
+
+fn is_false(value: &bool) -> bool {
+    !value
 }
 
 #[derive(Debug, PartialEq, Clone, Deserialize, Serialize)]
+pub enum Location {
+    #[serde(rename = "path")]
+    Path,
+    #[serde(rename = "query")]
+    Query,
+    #[serde(rename = "header")]
+    Header,
+    #[serde(rename = "cookie")]
+    Cookie,
+}
+
+#[derive(Debug, Default, PartialEq, Clone, Deserialize, Serialize)]
 pub enum VariableType {
     #[serde(rename = "String")]
     StringType,
@@ -19,10 +93,55 @@ pub enum VariableType {
     BoolType,
     #[serde(rename = "Float")]
     FloatType,
-    #[serde(rename = "Array")]
-    ArrayType(Box<VariableType>),
+    /// `integer` with `format: int64`
+    #[serde(rename = "Int64")]
+    Int64Type,
+    /// `number` with `format: double`
+    #[serde(rename = "Double")]
+    DoubleType,
+    #[serde(rename = "Date")]
+    DateType,
+    #[serde(rename = "DateTime")]
+    DateTimeType,
+    #[serde(rename = "Uuid")]
+    UuidType,
+    /// base64 encoded bytes
+    #[serde(rename = "Byte")]
+    ByteType,
+    /// raw octets, eg. a file upload
+    #[serde(rename = "Binary")]
+    BinaryType,
     #[serde(rename = "Complex")]
     ComplexType(String),
+    /// string restricted to the listed values
+    #[serde(rename = "Enum")]
+    EnumType(Vec<String>),
+    /// key and value types, eg. `{"Map":["String","Int"]}`
+    #[serde(rename = "Map")]
+    MapType(Box<VariableType>, Box<VariableType>),
+    /// arbitrary JSON
+    #[serde(rename = "Any")]
+    #[default]
+    AnyType,
+    /// serialised as a single-element list, eg. `["String"]`
+    #[serde(untagged, with = "single_element")]
+    ArrayType(Box<VariableType>),
+}
+
+mod single_element {
+    use super::VariableType;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(item: &VariableType, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(std::iter::once(item))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Box<VariableType>, D::Error> {
+        let [item] = <[VariableType; 1]>::deserialize(deserializer)?;
+        Ok(Box::new(item))
+    }
 }
 
 impl VariableType {
@@ -32,6 +151,13 @@ impl VariableType {
             VariableType::IntType => "INTEGER",
             VariableType::BoolType => "TINYINT",
             VariableType::FloatType => "REAL",
+            VariableType::Int64Type => "BIGINT",
+            VariableType::DoubleType => "DOUBLE",
+            VariableType::DateType => "DATE",
+            VariableType::DateTimeType => "TIMESTAMP",
+            VariableType::UuidType => "CHAR(36)",
+            VariableType::ByteType | VariableType::BinaryType => "BLOB",
+            VariableType::MapType(_, _) | VariableType::AnyType => "JSON",
             _ => "TEXT",
         }
         .to_string()
@@ -40,6 +166,29 @@ impl VariableType {
 
 #[test]
 fn test_json() {
-    assert_eq!(serde_json::to_string(&VariableType::StringType).unwrap(), "\"String\"");
-    assert_eq!(serde_json::to_string(&VariableType::ArrayType(Box::new(VariableType::StringType))).unwrap(), "[\"String\"]");
+    assert_eq!(
+        serde_json::to_string(&VariableType::StringType).unwrap(),
+        "\"String\""
+    );
+    assert_eq!(
+        serde_json::to_string(&VariableType::ArrayType(Box::new(VariableType::StringType)))
+            .unwrap(),
+        "[\"String\"]"
+    );
+    assert_eq!(
+        serde_json::to_string(&VariableType::EnumType(vec!["sold".to_string()])).unwrap(),
+        "{\"Enum\":[\"sold\"]}"
+    );
+    let map = VariableType::MapType(
+        Box::new(VariableType::StringType),
+        Box::new(VariableType::IntType),
+    );
+    assert_eq!(
+        serde_json::to_string(&map).unwrap(),
+        "{\"Map\":[\"String\",\"Int\"]}"
+    );
+    assert_eq!(
+        serde_json::from_str::<VariableType>("{\"Map\":[\"String\",\"Int\"]}").unwrap(),
+        map
+    );
 }
@@ -3,42 +3,143 @@ use crate::project::*;
 use crate::*;
 use log::*;
 use openapiv3::OpenAPI;
-use std::path::PathBuf;
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 
 pub struct ProjectGraph {
     config: Config,
+    spec_paths: Vec<PathBuf>,
     spec: OpenAPI,
+    /// keys declared with `x-cdd-key`/`x-keys`, by model name
+    keys: BTreeMap<String, Key>,
 }
 
 impl ProjectGraph {
-    pub fn read(path: &PathBuf) -> CliResult<Self> {
+    pub fn read(path: &Path) -> CliResult<Self> {
         let config = config::Config::read(path.join("config.yml"))?;
-        let spec = load_openapi_spec()?;
+        let spec_paths = config.spec_paths(path);
+        let spec = load_openapi_spec(&spec_paths)?;
+        let keys = declared_keys(&spec)?;
         Ok(ProjectGraph {
             config,
-            spec,
+            spec_paths,
+            spec: serde_json::from_value(spec)?,
+            keys,
         })
     }
 
-    /// super basic one way spec -> projects sync
+    fn spec_project(&self) -> CliResult<Project> {
+        let mut project = Project::parse_yml(self.spec.clone())?;
+        let schemas: Vec<String> = self
+            .spec
+            .components
+            .iter()
+            .flat_map(|components| components.schemas.keys().cloned())
+            .collect();
+        project.apply_keys(&self.keys, self.config.default_key.as_ref(), &schemas)?;
+        Ok(project)
+    }
+
+    /// code -> spec -> projects sync: models and requests added or changed in the code of
+    /// `service_name` are written into the spec, which is then synced to every service.
+    /// anything missing from its code is left in the spec.
+    pub fn sync_from(&mut self, service_name: &str) -> CliResult<()> {
+        let spec_graph = self.spec_project()?;
+        let service = self.config.services.get(service_name).ok_or_else(|| {
+            failure::format_err!("There is no service {} in config.yml", service_name)
+        })?;
+        let code = service
+            .extract_project(&service.request_files_for(&spec_graph))?
+            .snapshot();
+        let state_path = state_path(service_name);
+        let state = merge::SyncState::read(&state_path)?;
+        let base = state
+            .as_ref()
+            .map(|state| state.code.clone())
+            .unwrap_or_default();
+        let (models, requests) = code_changes(
+            &spec_graph,
+            &base,
+            code.models.clone(),
+            code.requests.clone(),
+        );
+
+        if models.is_empty() && requests.is_empty() {
+            info!("No changes to the spec in {}", service_name);
+        } else {
+            let spec_path = match self.spec_paths.as_slice() {
+                [spec_path] => spec_path.clone(),
+                _ => {
+                    return Err(failure::format_err!(
+                        "Cannot write changes from {} into the spec: openapi in config.yml lists several files",
+                        service_name
+                    ));
+                }
+            };
+            for model in models.iter() {
+                warn!(
+                    "Model {} from {} is new or changed, writing it to {}",
+                    model.name,
+                    service_name,
+                    spec_path.display()
+                );
+            }
+            for request in requests.iter() {
+                warn!(
+                    "Request {} from {} is new or changed, writing it to {}",
+                    request.name,
+                    service_name,
+                    spec_path.display()
+                );
+            }
+            let model_names: Vec<String> = models.iter().map(|model| model.name.clone()).collect();
+            let request_names: Vec<String> = requests
+                .iter()
+                .map(|request| request.name.clone())
+                .collect();
+            write_changes(&spec_path, spec_graph, models, requests)?;
+
+            let spec = load_openapi_spec(&self.spec_paths)?;
+            self.keys = declared_keys(&spec)?;
+            self.spec = serde_json::from_value(spec)?;
+
+            // the spec now holds the code's version, which the adaptor may report with fewer details
+            let spec_graph = self.spec_project()?;
+            let spec = merge::Snapshot {
+                models: spec_graph.models,
+                requests: spec_graph.requests,
+            };
+            state
+                .unwrap_or_else(|| merge::SyncState::unsynced(&code))
+                .after_write(&spec, &code, &model_names, &request_names)
+                .write(&state_path)?;
+        }
+
+        self.simple_sync()
+    }
+
+    /// spec -> projects sync: each service takes the spec's changes since its last sync,
+    /// keeping those made in its code
     pub fn simple_sync(&self) -> CliResult<()> {
-        let spec_graph = Project::parse_yml(self.spec.clone())?;
+        let spec_graph = self.spec_project()?;
 
         info!(
             "Found {} models, {} routes in {}",
             spec_graph.models.len(),
             spec_graph.requests.len(),
-            "openapi.yml"
+            self.spec_paths
+                .iter()
+                .map(|path| path.display().to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
         );
-        for (_name, service) in self.config.services.clone() {
-            service.sync_with(&spec_graph)?;
+        for (name, service) in self.config.services.clone() {
+            service.sync_with(&spec_graph, &state_path(&name))?;
             service.write_tests()?;
         }
 
-        util::write_file(
-            PathBuf::from("schema.sql"),
-            &schema::generate(spec_graph),
-        )?;
+        util::write_file(PathBuf::from("schema.sql"), &schema::generate(spec_graph))?;
 
         Ok(())
     }
@@ -66,14 +167,639 @@ impl ProjectGraph {
     }
 }
 
-fn load_openapi_spec() -> CliResult<OpenAPI> {
-    let spec_path: PathBuf = PathBuf::from("openapi.yml");
+/// where the state of a service's last sync is kept
+fn state_path(service_name: &str) -> PathBuf {
+    PathBuf::from(".cdd/state").join(format!("{}.json", service_name))
+}
+
+/// load and merge the spec files named in the config
+fn load_openapi_spec(spec_paths: &[PathBuf]) -> CliResult<Value> {
+    let mut merged: Option<Value> = None;
+    for spec_path in spec_paths {
+        let spec = load_spec_file(spec_path)?;
+        merged = Some(match merged {
+            Some(merged) => merge_specs(merged, spec, spec_path)?,
+            None => spec,
+        });
+    }
+    merged
+        .ok_or_else(|| failure::format_err!("No spec files are listed under openapi in config.yml"))
+}
 
+/// a single spec file, bundled and converted to OpenAPI 3.0
+fn load_spec_file(spec_path: &Path) -> CliResult<Value> {
+    match spec_path
+        .extension()
+        .and_then(|extension| extension.to_str())
+    {
+        Some("yaml") | Some("yml") | Some("json") => {}
+        _ => {
+            return Err(failure::format_err!(
+                "Unsupported spec file {}: expected a .yaml, .yml or .json file",
+                spec_path.display()
+            ));
+        }
+    }
     if !spec_path.exists() {
-        return Err(failure::format_err!("Could not find openapi.yml"));
+        return Err(failure::format_err!(
+            "Could not find {}",
+            spec_path.display()
+        ));
+    };
+
+    let mut spec = bundle::bundle(spec_path)?;
+    if convert::is_swagger(&spec) {
+        info!(
+            "Converting Swagger 2.0 spec {} to OpenAPI 3.0",
+            spec_path.display()
+        );
+        spec = convert::swagger_to_openapi(spec)?;
+    }
+    if convert::is_openapi_31(&spec) {
+        info!(
+            "Converting OpenAPI 3.1 spec {} to OpenAPI 3.0",
+            spec_path.display()
+        );
+        spec = convert::openapi_31_to_30(spec)?;
+    }
+    convert::inherit_security(&mut spec);
+
+    Ok(spec)
+}
+
+/// add the paths and components of `spec` to `merged`, which keeps its own info and servers.
+/// the same operation or component may appear in both only if they are identical.
+fn merge_specs(mut merged: Value, spec: Value, spec_path: &Path) -> CliResult<Value> {
+    for section in ["paths", "components"].iter() {
+        if let Some(from) = spec.get(*section) {
+            let into = merged
+                .as_object_mut()
+                .ok_or_else(|| failure::format_err!("Spec is not an object"))?
+                .entry(section.to_string())
+                .or_insert_with(|| Value::Object(Map::new()));
+            // operations and named components are merged whole
+            merge_maps(into, from, 1, section, spec_path)?;
+        }
+    }
+    Ok(merged)
+}
+
+fn merge_maps(
+    into: &mut Value,
+    from: &Value,
+    depth: usize,
+    location: &str,
+    spec_path: &Path,
+) -> CliResult<()> {
+    let (into, from) = match (into.as_object_mut(), from.as_object()) {
+        (Some(into), Some(from)) => (into, from),
+        _ => {
+            return Err(failure::format_err!(
+                "Could not merge {}: {} is not an object",
+                spec_path.display(),
+                location
+            ))
+        }
+    };
+    for (key, value) in from {
+        let location = format!("{}.{}", location, key);
+        match into.get_mut(key) {
+            None => {
+                into.insert(key.clone(), value.clone());
+            }
+            Some(existing) if existing == value => {}
+            Some(existing) if depth > 0 => {
+                merge_maps(existing, value, depth - 1, &location, spec_path)?
+            }
+            Some(_) => {
+                return Err(failure::format_err!(
+                    "Could not merge {}: {} is already defined differently",
+                    spec_path.display(),
+                    location
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// models and requests from code that are new to the spec or differ from it,
+/// leaving out those still as they were in `base`, the code at the last sync.
+/// keys, which code doesn't carry, are taken from the spec's version.
+fn code_changes(
+    spec_project: &Project,
+    base: &merge::Snapshot,
+    models: Vec<Model>,
+    requests: Vec<Request>,
+) -> (Vec<Model>, Vec<Request>) {
+    let mut changed_models = vec![];
+    for mut model in models {
+        if base.models.contains(&model) {
+            continue;
+        }
+        if let Some(spec_model) = spec_project
+            .models
+            .iter()
+            .find(|spec_model| spec_model.name == model.name)
+        {
+            if merge::same_model(spec_model, &model) {
+                continue;
+            }
+            if model.keys.is_empty() {
+                model.keys = spec_model.keys.clone();
+            }
+        }
+        changed_models.push(model);
+    }
+
+    let mut changed_requests = vec![];
+    for request in requests {
+        if base.requests.contains(&request) {
+            continue;
+        }
+        if let Some(spec_request) = spec_project
+            .requests
+            .iter()
+            .find(|spec_request| spec_request.name == request.name)
+        {
+            if *spec_request == request {
+                continue;
+            }
+        }
+        changed_requests.push(request);
+    }
+    (changed_models, changed_requests)
+}
+
+/// write models and requests into the spec file, replacing those of the same name.
+/// the file is rewritten whole, so any comments in it are lost.
+fn write_changes(
+    spec_path: &Path,
+    mut project: Project,
+    models: Vec<Model>,
+    requests: Vec<Request>,
+) -> CliResult<()> {
+    // yaml is a superset of json, so this reads either
+    let mut spec: Value = serde_yaml::from_str(&util::read_file(spec_path.to_path_buf())?)
+        .map_err(|e| failure::format_err!("Could not parse {}: {}", spec_path.display(), e))?;
+    if convert::is_swagger(&spec) || convert::is_openapi_31(&spec) {
+        return Err(failure::format_err!(
+            "Cannot write changes into {}: only OpenAPI 3.0 specs can be written to",
+            spec_path.display()
+        ));
+    }
+
+    for model in models.iter() {
+        project
+            .models
+            .retain(|existing| existing.name != model.name);
+        project.models.push(model.clone());
+    }
+    for model in models.iter() {
+        spec["components"]["schemas"][&model.name] = project.model_schema(model);
+    }
+    project::declare_keys(&mut spec, &models);
+
+    for request in requests.iter() {
+        // the request may have moved to another route or method
+        if let Some(existing) = project
+            .requests
+            .iter()
+            .find(|existing| existing.name == request.name)
+        {
+            if let Some(Value::Object(path_item)) = spec
+                .get_mut("paths")
+                .and_then(|paths| paths.get_mut(&existing.path))
+            {
+                path_item.remove(&existing.method.to_string().to_lowercase());
+                if path_item.is_empty() {
+                    if let Some(paths) = spec["paths"].as_object_mut() {
+                        paths.remove(&existing.path);
+                    }
+                }
+            }
+        }
+        let mut operation = project.operation(request);
+        // without it the operation would inherit the top-level requirements
+        if request.security.is_empty() && spec.get("security").is_some() {
+            operation["security"] = serde_json::json!([]);
+        }
+        spec["paths"][&request.path][request.method.to_string().to_lowercase()] = operation;
+    }
+
+    // models hoisted from inline schemas are only in the spec where they are used, so add
+    // any that what was written now refers to
+    let mut referenced = vec![];
+    models
+        .iter()
+        .for_each(|model| model_references(model, &mut referenced));
+    requests
+        .iter()
+        .for_each(|request| request_references(request, &mut referenced));
+    while let Some(name) = referenced.pop() {
+        if spec
+            .pointer(&format!("/components/schemas/{}", name))
+            .is_some()
+        {
+            continue;
+        }
+        if let Some(model) = project.models.iter().find(|model| model.name == name) {
+            spec["components"]["schemas"][&name] = project.model_schema(model);
+            model_references(model, &mut referenced);
+        }
+    }
+
+    let contents = match spec_path
+        .extension()
+        .and_then(|extension| extension.to_str())
+    {
+        Some("json") => serde_json::to_string_pretty(&spec)?,
+        _ => serde_yaml::to_string(&spec)?,
+    };
+    util::write_file(spec_path.to_path_buf(), &contents)
+}
+
+/// names of the models a type refers to
+fn type_references(variable_type: &VariableType, names: &mut Vec<String>) {
+    match variable_type {
+        VariableType::ComplexType(name) => names.push(name.clone()),
+        VariableType::ArrayType(item_type) => type_references(item_type, names),
+        VariableType::MapType(key_type, value_type) => {
+            type_references(key_type, names);
+            type_references(value_type, names);
+        }
+        _ => {}
+    }
+}
+
+fn model_references(model: &Model, names: &mut Vec<String>) {
+    model
+        .vars
+        .iter()
+        .for_each(|var| type_references(&var.variable_type, names));
+    names.extend(model.extends.iter().cloned());
+    if let Some(union) = &model.union {
+        names.extend(union.variants.iter().cloned());
+    }
+}
+
+/// names of the models a request's operation refers to, see `Project::operation`
+fn request_references(request: &Request, names: &mut Vec<String>) {
+    request
+        .vars
+        .iter()
+        .for_each(|var| type_references(&var.variable_type, names));
+    match &request.body {
+        Some(Body {
+            model: Some(model), ..
+        }) => model_references(model, names),
+        Some(body) => type_references(&body.body_type, names),
+        None => {}
+    }
+    for response in request.responses.iter() {
+        response
+            .response_type
+            .iter()
+            .for_each(|response_type| type_references(response_type, names));
+        response
+            .headers
+            .iter()
+            .for_each(|header| type_references(&header.variable_type, names));
+    }
+    if request.responses.is_empty() {
+        names.push(
+            request
+                .response_type
+                .trim_start_matches('[')
+                .trim_end_matches(']')
+                .to_string(),
+        );
+    }
+}
+
+#[test]
+fn test_load_spec_files() {
+    let dir = std::env::temp_dir().join(format!("cdd-specs-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("pets.yaml"), r#"
+openapi: 3.0.0
+info: { title: pets, version: 0.0.1 }
+paths:
+  /pets:
+    get:
+      responses:
+        '200': { description: ok, content: { application/json: { schema: { $ref: '#/components/schemas/Pet' } } } }
+components:
+  schemas:
+    Pet: { type: object, properties: { name: { type: string } } }
+"#).unwrap();
+    std::fs::write(dir.join("stores.json"), r##"{
+  "openapi": "3.0.0",
+  "info": { "title": "stores", "version": "0.0.1" },
+  "paths": {
+    "/pets": { "post": { "responses": { "201": { "description": "created" } } } },
+    "/stores": { "get": { "responses": { "200": { "description": "ok" } } } }
+  },
+  "components": {
+    "schemas": {
+      "Pet": { "type": "object", "properties": { "name": { "type": "string" } } },
+      "Store": { "type": "object", "properties": { "pets": { "type": "array", "items": { "$ref": "#/components/schemas/Pet" } } } }
+    }
+  }
+}"##).unwrap();
+
+    let spec = load_openapi_spec(&[dir.join("pets.yaml"), dir.join("stores.json")]).unwrap();
+    assert_eq!(spec["info"]["title"], "pets");
+    let project = Project::parse_yml(serde_json::from_value(spec).unwrap()).unwrap();
+    assert_eq!(project.models.len(), 2);
+    assert_eq!(project.requests.len(), 3);
+
+    std::fs::write(
+        dir.join("stores.json"),
+        r#"{ "openapi": "3.0.0", "info": { "title": "stores", "version": "0.0.1" },
+  "paths": {}, "components": { "schemas": { "Pet": { "type": "string" } } } }"#,
+    )
+    .unwrap();
+    let err = load_openapi_spec(&[dir.join("pets.yaml"), dir.join("stores.json")]).unwrap_err();
+    assert!(format!("{}", err).ends_with("components.schemas.Pet is already defined differently"));
+
+    let err = load_openapi_spec(&[dir.join("pets.txt")]).unwrap_err();
+    assert!(format!("{}", err).starts_with("Unsupported spec file"));
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_write_changes() {
+    let dir = std::env::temp_dir().join(format!("cdd-write-changes-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let spec_path = dir.join("openapi.yml");
+    std::fs::write(&spec_path, r#"
+openapi: 3.0.0
+info: { title: pets, version: 0.0.1 }
+security: [{ api_key: [] }]
+paths:
+  /pets:
+    get:
+      operationId: listPets
+      responses:
+        '200': { description: ok, content: { application/json: { schema: { type: array, items: { $ref: '#/components/schemas/Pet' } } } } }
+components:
+  securitySchemes:
+    api_key: { type: apiKey, in: header, name: X-API-Key }
+  schemas:
+    Pet:
+      type: object
+      x-cdd-key: name
+      required: [name]
+      properties:
+        name: { type: string }
+"#).unwrap();
+    let spec = load_openapi_spec(std::slice::from_ref(&spec_path)).unwrap();
+    let mut project = Project::parse_yml(serde_json::from_value(spec.clone()).unwrap()).unwrap();
+    project
+        .apply_keys(&declared_keys(&spec).unwrap(), None, &[])
+        .unwrap();
+
+    // as an adaptor would report them, without keys
+    let mut pet = project.models[0].clone();
+    pet.keys.clear();
+    let unchanged = pet.clone();
+    pet.vars.push(Box::new(Variable {
+        name: "age".to_string(),
+        variable_type: VariableType::IntType,
+        optional: true,
+        ..Default::default()
+    }));
+    let toy = Model {
+        name: "Toy".to_string(),
+        vars: unchanged.vars.clone(),
+        ..Default::default()
+    };
+    let list_pets = project.requests[0].clone();
+    let mut get_toy = list_pets.clone();
+    get_toy.name = "getToy".to_string();
+    get_toy.path = "/toys/{toyId}".to_string();
+    get_toy.security.clear();
+    get_toy.vars.push(Box::new(Variable {
+        name: "toyId".to_string(),
+        variable_type: VariableType::StringType,
+        location: Some(Location::Path),
+        ..Default::default()
+    }));
+
+    let (models, requests) = code_changes(
+        &project,
+        &merge::Snapshot::default(),
+        vec![unchanged, toy],
+        vec![list_pets, get_toy],
+    );
+    assert_eq!(
+        models
+            .iter()
+            .map(|model| model.name.as_str())
+            .collect::<Vec<_>>(),
+        vec!["Toy"]
+    );
+    assert_eq!(
+        requests
+            .iter()
+            .map(|request| request.name.as_str())
+            .collect::<Vec<_>>(),
+        vec!["getToy"]
+    );
+    let (changed, _) = code_changes(&project, &merge::Snapshot::default(), vec![pet], vec![]);
+    assert_eq!(changed.len(), 1);
+    write_changes(
+        &spec_path,
+        project,
+        models.into_iter().chain(changed).collect(),
+        requests,
+    )
+    .unwrap();
+
+    let spec = load_openapi_spec(std::slice::from_ref(&spec_path)).unwrap();
+    assert_eq!(spec["components"]["schemas"]["Pet"]["x-cdd-key"], "name");
+    let project = Project::parse_yml(serde_json::from_value(spec).unwrap()).unwrap();
+    let pet = project
+        .models
+        .iter()
+        .find(|model| model.name == "Pet")
+        .unwrap();
+    assert_eq!(
+        pet.vars
+            .iter()
+            .map(|var| var.name.as_str())
+            .collect::<Vec<_>>(),
+        vec!["name", "age"]
+    );
+    assert!(project.models.iter().any(|model| model.name == "Toy"));
+    let get_toy = project
+        .requests
+        .iter()
+        .find(|request| request.name == "getToy")
+        .unwrap();
+    assert_eq!(get_toy.path, "/toys/{toyId}");
+    // written with `security: []`, so it doesn't pick up the top-level requirement
+    assert!(get_toy.security.is_empty());
+    assert_eq!(
+        project
+            .requests
+            .iter()
+            .find(|request| request.name == "listPets")
+            .unwrap()
+            .security
+            .len(),
+        1
+    );
+    assert_eq!(project.requests.len(), 2);
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_write_hoisted_models() {
+    let dir = std::env::temp_dir().join(format!("cdd-write-hoisted-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let spec_path = dir.join("openapi.yml");
+    std::fs::write(
+        &spec_path,
+        r#"
+openapi: 3.0.0
+info: { title: pets, version: 0.0.1 }
+paths: {}
+components:
+  schemas:
+    Pet:
+      type: object
+      properties:
+        name: { type: string }
+        owner: { type: object, properties: { name: { type: string } } }
+"#,
+    )
+    .unwrap();
+    let spec = load_openapi_spec(std::slice::from_ref(&spec_path)).unwrap();
+    let project = Project::parse_yml(serde_json::from_value(spec).unwrap()).unwrap();
+    let mut pet = project
+        .models
+        .iter()
+        .find(|model| model.name == "Pet")
+        .unwrap()
+        .clone();
+    pet.vars.push(Box::new(Variable {
+        name: "age".to_string(),
+        variable_type: VariableType::IntType,
+        optional: true,
+        ..Default::default()
+    }));
+
+    let (models, requests) = code_changes(&project, &merge::Snapshot::default(), vec![pet], vec![]);
+    write_changes(&spec_path, project, models, requests).unwrap();
+
+    let spec = load_openapi_spec(std::slice::from_ref(&spec_path)).unwrap();
+    let project = Project::parse_yml(serde_json::from_value(spec).unwrap()).unwrap();
+    let pet = project
+        .models
+        .iter()
+        .find(|model| model.name == "Pet")
+        .unwrap();
+    assert_eq!(
+        pet.vars
+            .iter()
+            .map(|var| var.name.as_str())
+            .collect::<Vec<_>>(),
+        vec!["name", "owner", "age"]
+    );
+    assert_eq!(
+        pet.vars[1].variable_type,
+        VariableType::ComplexType("PetOwner".to_string())
+    );
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_sync_from_then_diff() {
+    let dir = std::env::temp_dir().join(format!("cdd-sync-from-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let spec_path = dir.join("openapi.yml");
+    std::fs::write(
+        &spec_path,
+        r#"
+openapi: 3.0.0
+info: { title: pets, version: 0.0.1 }
+paths: {}
+components:
+  schemas:
+    Pet:
+      type: object
+      properties:
+        name: { type: string }
+"#,
+    )
+    .unwrap();
+    let spec = load_openapi_spec(std::slice::from_ref(&spec_path)).unwrap();
+    let project = Project::parse_yml(serde_json::from_value(spec).unwrap()).unwrap();
+
+    // as an adaptor reports a new request: a path var without a location, and no responses
+    let get_pet: Request = serde_json::from_value(serde_json::json!({
+        "name": "getPetPhoto",
+        "path": "/pets/{id}/photo",
+        "vars": [{ "name": "id", "type": "String", "optional": false }],
+        "method": "GET",
+        "response_type": "Pet",
+    }))
+    .unwrap();
+    let code = merge::Snapshot {
+        models: project.models.clone(),
+        requests: vec![get_pet],
+    };
+    // last synced before the request was added
+    let state = merge::SyncState::unsynced(&merge::Snapshot {
+        models: project.models.clone(),
+        requests: vec![],
+    });
+
+    // what `sync_from` does, short of running the adaptor
+    let (models, requests) = code_changes(
+        &project,
+        &state.code,
+        code.models.clone(),
+        code.requests.clone(),
+    );
+    assert!(models.is_empty());
+    assert_eq!(requests.len(), 1);
+    write_changes(&spec_path, project, models, requests).unwrap();
+    let spec = load_openapi_spec(std::slice::from_ref(&spec_path)).unwrap();
+    assert_eq!(
+        spec["paths"]
+            .as_object()
+            .unwrap()
+            .keys()
+            .collect::<Vec<_>>(),
+        vec!["/pets/{id}/photo"]
+    );
+    let project = Project::parse_yml(serde_json::from_value(spec).unwrap()).unwrap();
+    assert_eq!(project.requests[0].path, "/pets/{id}/photo");
+    assert_eq!(project.requests[0].vars[0].location, Some(Location::Path));
+    assert_ne!(project.requests, code.requests);
+    let spec = merge::Snapshot {
+        models: project.models.clone(),
+        requests: project.requests.clone(),
     };
+    let state = state.after_write(&spec, &code, &[], &["getPetPhoto".to_string()]);
 
-    let spec = std::fs::read_to_string(spec_path)?;
+    assert_eq!(state.diff(&spec, &code), merge::Diff::default());
+    // nor is it written again
+    assert_eq!(
+        code_changes(
+            &project,
+            &state.code,
+            code.models.clone(),
+            code.requests.clone()
+        ),
+        (vec![], vec![])
+    );
 
-    Ok(serde_yaml::from_str(&spec)?)
+    std::fs::remove_dir_all(dir).unwrap();
 }
@@ -1,52 +1,271 @@
 use crate::*;
 use log::*;
+use std::collections::HashMap;
 
 pub fn generate(project: Project) -> String {
     info!("Generating sql migration schema");
+    // enum components, so columns referencing them by name get a CHECK constraint too
+    let enums: HashMap<String, Vec<String>> = project
+        .models
+        .iter()
+        .filter_map(|model| {
+            model
+                .enumeration
+                .clone()
+                .map(|values| (model.name.clone(), values))
+        })
+        .collect();
+
     project
         .models
         .into_iter()
-        .map(model_to_sql)
+        // unions are stored through their variants' tables, enums as CHECK constraints
+        .filter(Model::is_object)
+        .map(|model| model_to_sql(model, &enums))
         .collect::<Vec<String>>()
         .join("\n")
 }
 
-fn model_to_sql(model: Model) -> String {
+fn model_to_sql(model: Model, enums: &HashMap<String, Vec<String>>) -> String {
     format!(
         "CREATE TABLE {}{};\n",
         model.name.to_lowercase(),
-        vars_to_sql(model.vars)
+        vars_to_sql(&model.vars, &model.keys, enums)
     )
 }
 
-fn vars_to_sql(vars: Vec<Box<Variable>>) -> String {
+fn vars_to_sql(
+    vars: &[Box<Variable>],
+    keys: &[String],
+    enums: &HashMap<String, Vec<String>>,
+) -> String {
     if vars.is_empty() {
         return "".to_string();
     }
 
-    format!(
-        "(\n{}\n)", // this is a hack for primary key support - need to support x-keys
-        vars.into_iter()
-            .map(|m| *m)
-            .map(var_to_sql)
-            .collect::<Vec<String>>()
-            .join(",\n"),
-    )
+    // a single key is declared on its column, composite keys as a table constraint
+    let mut columns: Vec<String> = vars
+        .iter()
+        .map(|m| var_to_sql(m, keys.len() == 1 && keys[0] == m.name, enums))
+        .collect();
+    if keys.len() > 1 {
+        columns.push(format!("\tPRIMARY KEY ({})", keys.join(", ")));
+    }
+
+    format!("(\n{}\n)", columns.join(",\n"))
 }
 
-fn var_to_sql(var: Variable) -> String {
-    println!("--> {:?}", var);
-    let not_null = if var.optional {
+fn var_to_sql(var: &Variable, is_key: bool, enums: &HashMap<String, Vec<String>>) -> String {
+    // absent values are stored as NULL unless there is a default to fall back on
+    let not_null = if var.nullable || (var.optional && var.value.is_none()) {
         ""
     } else {
         " NOT NULL"
     };
 
-    let is_pk = if var.name == "id".to_string() {
-        " PRIMARY KEY AUTOINCREMENT"
+    let default = match (&var.value, &var.variable_type) {
+        (None, _) => "".to_string(),
+        (
+            Some(value),
+            VariableType::IntType
+            | VariableType::Int64Type
+            | VariableType::FloatType
+            | VariableType::DoubleType
+            | VariableType::BoolType,
+        ) => format!(" DEFAULT {}", value),
+        (Some(value), _) => format!(" DEFAULT {}", quote(value)),
+    };
+
+    let is_pk = match &var.variable_type {
+        _ if !is_key => "",
+        VariableType::IntType | VariableType::Int64Type => " PRIMARY KEY AUTOINCREMENT",
+        _ => " PRIMARY KEY",
+    };
+
+    let values = match &var.variable_type {
+        VariableType::EnumType(values) => Some(values),
+        VariableType::ComplexType(name) => enums.get(name),
+        _ => None,
+    };
+    let mut conditions: Vec<String> = vec![];
+    if let Some(values) = values {
+        let values: Vec<String> = values.iter().map(|value| quote(value)).collect();
+        conditions.push(format!("{} IN ({})", var.name, values.join(", ")));
+    }
+
+    let constraints = &var.constraints;
+    if let Some(minimum) = constraints.minimum {
+        let operator = if constraints.exclusive_minimum {
+            ">"
+        } else {
+            ">="
+        };
+        conditions.push(format!("{} {} {}", var.name, operator, minimum));
+    }
+    if let Some(maximum) = constraints.maximum {
+        let operator = if constraints.exclusive_maximum {
+            "<"
+        } else {
+            "<="
+        };
+        conditions.push(format!("{} {} {}", var.name, operator, maximum));
+    }
+    if let Some(multiple_of) = constraints.multiple_of {
+        conditions.push(format!("MOD({}, {}) = 0", var.name, multiple_of));
+    }
+    if let Some(min_length) = constraints.min_length {
+        conditions.push(format!("CHAR_LENGTH({}) >= {}", var.name, min_length));
+    }
+    if let Some(pattern) = &constraints.pattern {
+        conditions.push(format!("{} REGEXP {}", var.name, quote(pattern)));
+    }
+    let check = if conditions.is_empty() {
+        "".to_string()
     } else {
-        ""
+        format!(" CHECK ({})", conditions.join(" AND "))
     };
 
-    format!("\t{} {}{}{}", var.name, var.variable_type.to_mysql(), is_pk, not_null)
+    // maxLength is enforced by the column type
+    let sql_type = match (&var.variable_type, constraints.max_length) {
+        (VariableType::StringType, Some(max_length)) => format!("VARCHAR({})", max_length),
+        (variable_type, _) => variable_type.to_mysql(),
+    };
+
+    format!(
+        "\t{} {}{}{}{}{}",
+        var.name, sql_type, is_pk, not_null, default, check
+    )
+}
+
+/// a SQL string literal
+fn quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+#[test]
+fn test_enum_check_constraint() {
+    let status = Variable {
+        name: "status".to_string(),
+        variable_type: VariableType::EnumType(vec!["available".to_string(), "sold".to_string()]),
+        optional: true,
+        ..Default::default()
+    };
+    assert_eq!(
+        var_to_sql(&status, false, &HashMap::new()),
+        "\tstatus TEXT CHECK (status IN ('available', 'sold'))"
+    );
+
+    let mut enums = HashMap::new();
+    enums.insert("Size".to_string(), vec!["o'large".to_string()]);
+    let size = Variable {
+        name: "size".to_string(),
+        variable_type: VariableType::ComplexType("Size".to_string()),
+        optional: false,
+        ..Default::default()
+    };
+    assert_eq!(
+        var_to_sql(&size, false, &enums),
+        "\tsize TEXT NOT NULL CHECK (size IN ('o''large'))"
+    );
+}
+
+#[test]
+fn test_validation_constraints() {
+    let age = Variable {
+        name: "age".to_string(),
+        variable_type: VariableType::IntType,
+        constraints: Constraints {
+            minimum: Some(0.0),
+            maximum: Some(30.0),
+            exclusive_maximum: true,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    assert_eq!(
+        var_to_sql(&age, false, &HashMap::new()),
+        "\tage INTEGER NOT NULL CHECK (age >= 0 AND age < 30)"
+    );
+
+    let code = Variable {
+        name: "code".to_string(),
+        variable_type: VariableType::StringType,
+        optional: true,
+        constraints: Constraints {
+            min_length: Some(2),
+            max_length: Some(8),
+            pattern: Some("^[a-z']+$".to_string()),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    assert_eq!(
+        var_to_sql(&code, false, &HashMap::new()),
+        "\tcode VARCHAR(8) CHECK (CHAR_LENGTH(code) >= 2 AND code REGEXP '^[a-z'']+$')"
+    );
+}
+
+#[test]
+fn test_not_null() {
+    let column = |optional: bool, nullable: bool, value: Option<&str>| {
+        let var = Variable {
+            name: "tag".to_string(),
+            variable_type: VariableType::StringType,
+            optional,
+            nullable,
+            value: value.map(String::from),
+            ..Default::default()
+        };
+        var_to_sql(&var, false, &HashMap::new())
+    };
+    assert_eq!(column(false, false, None), "\ttag TEXT NOT NULL");
+    assert_eq!(column(false, true, None), "\ttag TEXT");
+    assert_eq!(column(true, false, None), "\ttag TEXT");
+    assert_eq!(
+        column(true, false, Some("none")),
+        "\ttag TEXT NOT NULL DEFAULT 'none'"
+    );
+
+    let count = Variable {
+        name: "count".to_string(),
+        variable_type: VariableType::IntType,
+        optional: true,
+        value: Some("0".to_string()),
+        ..Default::default()
+    };
+    assert_eq!(
+        var_to_sql(&count, false, &HashMap::new()),
+        "\tcount INTEGER NOT NULL DEFAULT 0"
+    );
+}
+
+#[test]
+fn test_primary_keys() {
+    let var = |name: &str, variable_type: VariableType| {
+        Box::new(Variable {
+            name: name.to_string(),
+            variable_type,
+            ..Default::default()
+        })
+    };
+    let vars = vec![
+        var("id", VariableType::UuidType),
+        var("owner", VariableType::IntType),
+    ];
+    assert_eq!(
+        vars_to_sql(&vars, &["id".to_string()], &HashMap::new()),
+        "(\n\tid CHAR(36) PRIMARY KEY NOT NULL,\n\towner INTEGER NOT NULL\n)"
+    );
+    assert_eq!(
+        vars_to_sql(
+            &vars,
+            &["id".to_string(), "owner".to_string()],
+            &HashMap::new()
+        ),
+        "(\n\tid CHAR(36) NOT NULL,\n\towner INTEGER NOT NULL,\n\tPRIMARY KEY (id, owner)\n)"
+    );
+    assert_eq!(
+        var_to_sql(&var("id", VariableType::IntType), true, &HashMap::new()),
+        "\tid INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL"
+    );
 }
@@ -2,7 +2,7 @@ use crate::project::*;
 use crate::*;
 use log::*;
 use serde::{Deserialize, Serialize};
-use serde_json;
+use std::path::Path;
 
 #[derive(Serialize, Deserialize, Clone)]
 pub(crate) struct CDDService {
@@ -12,21 +12,49 @@ pub(crate) struct CDDService {
     pub project_path: String,
     pub component_file: String,
     pub requests_file: String,
+    /// file for each tag's requests, eg. `API/{tag}Requests.ts`; untagged requests stay in `requests_file`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tag_requests_file: Option<String>,
+}
+
+/// what an adaptor found in a project
+pub(crate) struct ExtractedProject {
+    pub models: Vec<Model>,
+    /// (file, request)
+    pub requests: Vec<(String, Request)>,
+}
+
+impl ExtractedProject {
+    pub fn snapshot(&self) -> merge::Snapshot {
+        merge::Snapshot {
+            models: self.models.clone(),
+            requests: self
+                .requests
+                .iter()
+                .map(|(_, request)| request.clone())
+                .collect(),
+        }
+    }
 }
 
 impl CDDService {
     /// request the adaptor creates new project files
+    #[allow(dead_code)]
     pub fn create_template(&self) -> CliResult<()> {
         self.exec(vec!["create-template", &self.project_path])
             .map(|_| ())
     }
 
-    pub fn sync_with(&self, spec_project: &Project) -> CliResult<()> {
-        let project = self.extract_project()?;
+    /// three-way sync of the spec into the project against the state at `state_path` of its last sync.
+    /// changes made only in code are left for `cdd sync --from`; conflicting changes stop the sync.
+    pub fn sync_with(&self, spec_project: &Project, state_path: &Path) -> CliResult<()> {
+        let project = self.extract_project(&self.request_files_for(spec_project))?;
         let project_model_names = project.models.all_names();
-        let spec_model_names = spec_project.models.all_names();
-        let project_request_names = project.requests.all_names();
-        let spec_request_names = spec_project.requests.all_names();
+        let project_request_names: Vec<String> = project
+            .requests
+            .iter()
+            .map(|(_, request)| request.name.clone())
+            .collect();
 
         info!(
             "Found {} models ({}), {} requests ({}) in {}",
@@ -37,75 +65,142 @@ impl CDDService {
             self.project_path,
         );
 
-        for model in project_model_names
+        let spec = merge::Snapshot {
+            models: spec_project.models.clone(),
+            requests: spec_project.requests.clone(),
+        };
+        let code = project.snapshot();
+        // never synced: take the spec's version of everything, as if the code was last synced from it
+        let state = merge::SyncState::read(state_path)?
+            .unwrap_or_else(|| merge::SyncState::unsynced(&code));
+        let diff = state.diff(&spec, &code);
+
+        let conflicts: Vec<String> = diff
+            .models
             .iter()
-            .filter(|model_name| !spec_model_names.contains(model_name))
-        {
-            self.delete_model(&model)?;
+            .filter(|(_, change)| *change == merge::Change::Conflict)
+            .map(|(name, _)| format!("model {}", name))
+            .chain(
+                diff.requests
+                    .iter()
+                    .filter(|(_, change)| *change == merge::Change::Conflict)
+                    .map(|(name, _)| format!("request {}", name)),
+            )
+            .collect();
+        if !conflicts.is_empty() {
+            return Err(failure::format_err!(
+                "Conflicting changes to {} in the spec and {}: make them match, or remove {} to take the spec's version",
+                conflicts.join(", "),
+                self.project_path,
+                state_path.display()
+            ));
         }
 
-        for model in spec_project.models.iter() {
-            let model_name = &model.name;
-            if project_model_names.contains(model_name) {
-                info!("Model {} was found in project", model_name);
-                for line in self.update_model(model.clone())?.lines() {
-                    info!("{}", line);
+        for (model_name, change) in diff.models.iter() {
+            match (
+                change.side(),
+                spec_project
+                    .models
+                    .iter()
+                    .find(|model| &model.name == model_name),
+            ) {
+                (Some(merge::Side::Spec), Some(model))
+                    if project_model_names.contains(model_name) =>
+                {
+                    info!("Model {} was {}, updating...", model_name, change);
+                    for line in self.update_model(model.clone())?.lines() {
+                        info!("{}", line);
+                    }
                 }
-            } else {
-                warn!(
-                    "Model {} was not found in project, inserting...",
-                    &model_name
-                );
-                for line in self.insert_model(model.clone())?.lines() {
-                    info!("{}", line);
+                (Some(merge::Side::Spec), Some(model)) => {
+                    warn!("Model {} was {}, inserting...", model_name, change);
+                    for line in self.insert_model(model.clone())?.lines() {
+                        info!("{}", line);
+                    }
                 }
+                (Some(merge::Side::Spec), None) => {
+                    if project_model_names.contains(model_name) {
+                        warn!("Model {} was {}, deleting...", model_name, change);
+                        self.delete_model(model_name)?;
+                    }
+                }
+                _ => warn!("Model {} was {}, leaving it as it is", model_name, change),
             }
         }
 
-        for request in project_request_names
-            .iter()
-            .filter(|request_name| !spec_request_names.contains(request_name))
-        {
-            self.delete_request(&request)?;
-        }
-
-        for request in spec_project.requests.iter() {
-            let request_name = &request.name;
-            if project_request_names.contains(request_name) {
-                info!("Request {} was found in project", request_name);
+        for (request_name, change) in diff.requests.iter() {
+            if change.side() != Some(merge::Side::Spec) {
+                warn!(
+                    "Request {} was {}, leaving it as it is",
+                    request_name, change
+                );
+                continue;
+            }
+            let found = project
+                .requests
+                .iter()
+                .find(|(_, request)| &request.name == request_name);
+            let request = spec_project
+                .requests
+                .iter()
+                .find(|request| &request.name == request_name);
+            let file = request.map(|request| self.request_file(request));
+            if let Some((found_in, _)) = found {
+                // also when its tags moved it to another file
+                if file.as_ref() != Some(found_in) {
+                    warn!(
+                        "Request {} was {}, deleting from {}...",
+                        request_name, change, found_in
+                    );
+                    self.delete_request(found_in, request_name)?;
+                }
+            }
+            let request = match request {
+                Some(request) => request,
+                None => continue,
+            };
+            if found.is_some_and(|(found_in, _)| Some(found_in) == file.as_ref()) {
+                info!("Request {} was {}, updating...", request_name, change);
                 for line in self.update_request(request.clone())?.lines() {
                     info!("{}", line);
                 }
             } else {
-                warn!(
-                    "Request {} was not found in project, inserting...",
-                    &request_name
-                );
+                warn!("Request {} was {}, inserting...", request_name, change);
                 for line in self.insert_request(request.clone())?.lines() {
                     info!("{}", line);
                 }
             }
         }
 
-        Ok(())
+        let synced = self.extract_project(&self.request_files_for(spec_project))?;
+        state
+            .after_sync(&diff, spec, synced.snapshot())
+            .write(state_path)
     }
 
     pub fn write_tests(&self) -> CliResult<String> {
         info!("Writing tests for {}", self.project_path);
 
-        Ok(self.exec(vec!["generate-tests"])?)
+        self.exec(vec!["generate-tests"])
     }
 
-    pub fn extract_project(&self) -> CliResult<Project> {
+    /// models and requests in the project, with the file each request was found in
+    pub fn extract_project(&self, request_files: &[String]) -> CliResult<ExtractedProject> {
         info!("Extracting objects from {}", self.project_path);
 
-        Ok(Project {
+        let mut requests = vec![];
+        for file in request_files {
+            // files for new tags won't be there yet
+            if *file == self.request_files() || util::file_exists(file) {
+                for request in self.extract_requests(file)? {
+                    requests.push((file.clone(), request));
+                }
+            }
+        }
+
+        Ok(ExtractedProject {
             models: self.extract_models()?,
-            requests: self.extract_requests()?,
-            info: crate::project::Info {
-                host: "".to_string(),
-                endpoint: "".to_string(),
-            },
+            requests,
         })
     }
 
@@ -117,56 +212,116 @@ impl CDDService {
         [self.project_path.clone(), self.requests_file.clone()].join("/")
     }
 
+    /// file a request belongs in, grouped by its first tag when `tag_requests_file` is set
+    pub fn request_file(&self, request: &Request) -> String {
+        match (&self.tag_requests_file, request.tags.first()) {
+            (Some(tag_requests_file), Some(tag)) => {
+                let file = tag_requests_file.replace("{tag}", &project::pascal_case(tag));
+                [self.project_path.clone(), file].join("/")
+            }
+            _ => self.request_files(),
+        }
+    }
+
+    /// every file requests of `spec_project` may be in, including those of tags it no longer uses
+    pub fn request_files_for(&self, spec_project: &Project) -> Vec<String> {
+        let mut files: Vec<String> = spec_project
+            .requests
+            .iter()
+            .map(|request| self.request_file(request))
+            .collect();
+        files.extend(self.existing_request_files());
+        files.sort();
+        files.dedup();
+        files
+    }
+
+    /// every requests file already in the project
+    pub fn existing_request_files(&self) -> Vec<String> {
+        let mut files = self.tag_request_files();
+        files.push(self.request_files());
+        files.sort();
+        files.dedup();
+        files
+    }
+
+    /// files in the project matching `tag_requests_file`, whose `{tag}` must be in the file name
+    fn tag_request_files(&self) -> Vec<String> {
+        let path = match &self.tag_requests_file {
+            Some(tag_requests_file) => {
+                [self.project_path.clone(), tag_requests_file.clone()].join("/")
+            }
+            None => return vec![],
+        };
+        let (dir, file) = path.rsplit_once('/').unwrap_or((".", &path));
+        let (prefix, suffix) = match file.split_once("{tag}") {
+            Some(parts) => parts,
+            None => return vec![],
+        };
+        // a missing directory just has no files yet
+        std::fs::read_dir(dir)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter_map(|entry| entry.file_name().into_string().ok())
+            .filter(|name| {
+                name.len() > prefix.len() + suffix.len()
+                    && name.starts_with(prefix)
+                    && name.ends_with(suffix)
+            })
+            .map(|name| [dir, &name].join("/"))
+            .collect()
+    }
+
     pub fn extract_models(&self) -> CliResult<Vec<Model>> {
         info!("Extracting models from {}", self.model_files());
         self.exec(vec!["list-models", &self.model_files()])
             .and_then(|json| {
-                Ok(serde_json::from_str::<Vec<Model>>(&json).map_err(|e| {
-                    failure::format_err!("Error parsing JSON result: {}\n{}", e, json)
-                })?)
+                serde_json::from_str::<Vec<Model>>(&json)
+                    .map_err(|e| failure::format_err!("Error parsing JSON result: {}\n{}", e, json))
             })
     }
 
-    pub fn extract_requests(&self) -> CliResult<Vec<Request>> {
-        info!("Extracting requests from {}", self.request_files());
-        self.exec(vec!["list-requests", &self.request_files()])
+    pub fn extract_requests(&self, file: &str) -> CliResult<Vec<Request>> {
+        info!("Extracting requests from {}", file);
+        self.exec(vec!["list-requests", file])
             .and_then(|json| Ok(serde_json::from_str::<Vec<Request>>(&json)?))
     }
 
     pub fn insert_model(&self, model: Model) -> CliResult<String> {
         info!("Inserting model {}", model.name);
-        Ok(self.exec(vec![
+        self.exec(vec![
             "insert-model",
             &self.model_files(),
             &serde_json::to_string(&model)?,
-        ])?)
+        ])
     }
 
     pub fn insert_request(&self, request: Request) -> CliResult<String> {
         info!("Inserting request {}", request.name);
-        Ok(self.exec(vec![
+        self.exec(vec![
             "insert-request",
-            &self.request_files(),
+            &self.request_file(&request),
             &serde_json::to_string(&request)?,
-        ])?)
+        ])
     }
 
     pub fn update_model(&self, model: Model) -> CliResult<String> {
         info!("Updating model {}", model.name);
-        Ok(self.exec(vec![
+        self.exec(vec![
             "update-model",
             &self.model_files(),
             &serde_json::to_string(&model)?,
-        ])?)
+        ])
     }
 
     pub fn update_request(&self, request: Request) -> CliResult<String> {
         info!("Updating request {}", request.name);
-        Ok(self.exec(vec![
+        self.exec(vec![
             "update-request",
-            &self.request_files(),
+            &self.request_file(&request),
             &serde_json::to_string(&request)?,
-        ])?)
+        ])
     }
 
     pub fn delete_model(&self, name: &str) -> CliResult<String> {
@@ -174,9 +329,9 @@ impl CDDService {
         self.exec(vec!["delete-model", &self.model_files(), name])
     }
 
-    pub fn delete_request(&self, name: &str) -> CliResult<String> {
-        warn!("Deleting request {}", name);
-        self.exec(vec!["delete-request", &self.request_files(), name])
+    pub fn delete_request(&self, file: &str, name: &str) -> CliResult<String> {
+        warn!("Deleting request {} from {}", name, file);
+        self.exec(vec!["delete-request", file, name])
     }
 
     fn exec(&self, args: Vec<&str>) -> CliResult<String> {
@@ -192,3 +347,70 @@ impl CDDService {
         util::exec(&bin_path, args)
     }
 }
+
+#[test]
+fn test_request_files_by_tag() {
+    let spec = r#"
+openapi: 3.0.0
+info: { title: test, version: 0.0.1 }
+paths:
+  /pets:
+    get: { tags: [pet store, pets], responses: { '200': { description: ok } } }
+  /health:
+    get: { responses: { '200': { description: ok } } }
+"#;
+    let project = Project::parse_yml(serde_yaml::from_str(spec).unwrap()).unwrap();
+    let mut service = CDDService {
+        bin_path: "~/.cdd/bin/cdd-typescript".to_string(),
+        address: "localhost:4444".to_string(),
+        template_path: "~/.cdd/templates/typescript".to_string(),
+        project_path: "./typescript".to_string(),
+        component_file: "API/Models.ts".to_string(),
+        requests_file: "API/Requests.ts".to_string(),
+        tag_requests_file: None,
+    };
+    assert_eq!(project.requests[0].tags, vec!["pet store", "pets"]);
+    assert_eq!(
+        service.request_files_for(&project),
+        vec!["./typescript/API/Requests.ts"]
+    );
+
+    service.tag_requests_file = Some("API/{tag}Requests.ts".to_string());
+    assert_eq!(
+        service.request_file(&project.requests[0]),
+        "./typescript/API/PetStoreRequests.ts"
+    );
+    assert_eq!(
+        service.request_file(&project.requests[1]),
+        "./typescript/API/Requests.ts"
+    );
+    assert_eq!(
+        service.request_files_for(&project),
+        vec![
+            "./typescript/API/PetStoreRequests.ts",
+            "./typescript/API/Requests.ts"
+        ]
+    );
+
+    // files of tags the spec no longer uses are still read, so their requests can be removed
+    let dir = std::env::temp_dir().join(format!("cdd-request-files-{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("API")).unwrap();
+    for file in ["Models.ts", "Requests.ts", "PetsRequests.ts"].iter() {
+        std::fs::write(dir.join("API").join(file), "").unwrap();
+    }
+    service.project_path = dir.display().to_string();
+    let in_dir = |file: &str| format!("{}/API/{}", dir.display(), file);
+    assert_eq!(
+        service.existing_request_files(),
+        vec![in_dir("PetsRequests.ts"), in_dir("Requests.ts")]
+    );
+    assert_eq!(
+        service.request_files_for(&project),
+        vec![
+            in_dir("PetStoreRequests.ts"),
+            in_dir("PetsRequests.ts"),
+            in_dir("Requests.ts")
+        ]
+    );
+    std::fs::remove_dir_all(dir).unwrap();
+}